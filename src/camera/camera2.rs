@@ -45,10 +45,7 @@ impl Camera {
     }
 
     fn input(&mut self, event: &WindowEvent) -> bool {
-        match event {
-            WindowEvent::CursorMoved { position, .. } => true,
-            _ => false,
-        }
+        matches!(event, WindowEvent::CursorMoved { .. })
     }
 
     fn update(&mut self, event: &WindowEvent) {
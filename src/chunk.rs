@@ -7,6 +7,8 @@ pub struct Chunk {
     z: i32,
 
     mesh: Option<Mesh>,
+    /// Set when a block changes, until the mesh is regenerated.
+    dirty: bool,
 }
 
 impl Chunk {
@@ -21,6 +23,7 @@ impl Chunk {
             y,
             z,
             mesh: None,
+            dirty: true,
         }
     }
 
@@ -36,8 +39,26 @@ impl Chunk {
         chunk
     }
 
+    pub fn position(&self) -> (i32, i32, i32) {
+        (self.x, self.y, self.z)
+    }
+
     pub fn generate_mesh(&mut self) {
         self.mesh = Some(Mesh::from_chunk(self));
+        self.dirty = false;
+    }
+
+    pub fn mesh(&self) -> Option<&Mesh> {
+        self.mesh.as_ref()
+    }
+
+    /// Whether the mesh is missing or out of date.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
     }
 
     pub fn indexof(x: usize, y: usize, z: usize) -> usize {
@@ -50,5 +71,6 @@ impl Chunk {
 
     pub fn set(&mut self, x: usize, y: usize, z: usize, v: u16) {
         self.data[Self::indexof(x, y, z)] = v;
+        self.dirty = true;
     }
 }
@@ -8,15 +8,16 @@ use winit::{
 };
 
 mod camera;
+mod chunk;
 mod cuboid;
 mod mesh;
-// mod network;
-mod chunk;
+mod network;
 mod pipeline;
 mod state;
 mod texture;
 mod uniform;
 mod vertex;
+mod world;
 
 fn main() {
     env_logger::init();
@@ -29,29 +30,25 @@ fn main() {
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == state.window().id() => {
-            if !state.input(event) {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            },
+        } if window_id == state.window().id() && !state.input(event) => match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(VirtualKeyCode::Escape),
                         ..
-                    } => control_flow.set_exit(),
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(*physical_size);
-                    }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        state.resize(**new_inner_size);
-                    }
-                    _ => {}
-                }
+                    },
+                ..
+            } => control_flow.set_exit(),
+            WindowEvent::Resized(physical_size) => {
+                state.resize(*physical_size);
             }
-        }
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                state.resize(**new_inner_size);
+            }
+            _ => {}
+        },
         Event::RedrawRequested(window_id) if window_id == state.window().id() => {
             state.update();
             match state.render() {
@@ -0,0 +1,318 @@
+use crate::network::enums::AdvancementFrame;
+use crate::network::packets::Advancements;
+use crate::network::read_write::{check_array_length, read_optional, write_optional};
+use crate::network::{Chat, Identifier, Index, MinecraftIo, Slot, VarInt};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+pub const FLAG_BACKGROUND_TEXTURE: i32 = 0x01;
+pub const FLAG_SHOW_TOAST: i32 = 0x02;
+pub const FLAG_HIDDEN: i32 = 0x04;
+
+/// How an advancement is shown in the advancements screen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AdvancementDisplay {
+    pub title: Chat,
+    pub description: Chat,
+    pub icon: Slot,
+    pub frame_type: AdvancementFrame,
+    pub flags: i32,
+    /// Only sent for root advancements, with `FLAG_BACKGROUND_TEXTURE`.
+    pub background_texture: Option<Identifier>,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl MinecraftIo for AdvancementDisplay {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.title.minecraft_write(writer)?;
+        self.description.minecraft_write(writer)?;
+        self.icon.minecraft_write(writer)?;
+        self.frame_type.minecraft_write(writer)?;
+        self.flags.minecraft_write(writer)?;
+        if self.flags & FLAG_BACKGROUND_TEXTURE != 0 {
+            self.background_texture
+                .clone()
+                .unwrap_or_default()
+                .minecraft_write(writer)?;
+        }
+        self.x.minecraft_write(writer)?;
+        self.y.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let title = Chat::minecraft_read(reader)?;
+        let description = Chat::minecraft_read(reader)?;
+        let icon = Slot::minecraft_read(reader)?;
+        let frame_type = AdvancementFrame::minecraft_read(reader)?;
+        let flags = i32::minecraft_read(reader)?;
+        let background_texture = if flags & FLAG_BACKGROUND_TEXTURE != 0 {
+            Some(Identifier::minecraft_read(reader)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            title,
+            description,
+            icon,
+            frame_type,
+            flags,
+            background_texture,
+            x: f32::minecraft_read(reader)?,
+            y: f32::minecraft_read(reader)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Advancement {
+    pub parent: Option<Identifier>,
+    pub display: Option<AdvancementDisplay>,
+    pub criteria: Vec<Identifier>,
+    /// The advancement is done when every group has at least one completed
+    /// criterion.
+    pub requirements: Vec<Vec<String>>,
+}
+
+impl MinecraftIo for Advancement {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        write_optional(&self.parent, writer)?;
+        write_optional(&self.display, writer)?;
+        self.criteria.minecraft_write(writer)?;
+        self.requirements.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            parent: read_optional(reader)?,
+            display: read_optional(reader)?,
+            criteria: <Vec<Identifier>>::minecraft_read(reader)?,
+            requirements: <Vec<Vec<String>>>::minecraft_read(reader)?,
+        })
+    }
+}
+
+/// The criteria of an advancement, each with the time it was achieved in
+/// milliseconds since the epoch, if it was.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AdvancementProgress {
+    pub criteria: Vec<(Identifier, Option<i64>)>,
+}
+
+impl MinecraftIo for AdvancementProgress {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        VarInt(self.criteria.len() as i32).minecraft_write(writer)?;
+        for (criterion, achieved) in &self.criteria {
+            criterion.minecraft_write(writer)?;
+            write_optional(achieved, writer)?;
+        }
+        Ok(())
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let length = check_array_length(VarInt::minecraft_read(reader)?.into_index()?)?;
+        let mut criteria = Vec::new();
+        for _ in 0..length {
+            criteria.push((Identifier::minecraft_read(reader)?, read_optional(reader)?));
+        }
+        Ok(Self { criteria })
+    }
+}
+
+/// The player's advancements and their progress, kept up to date from
+/// `Advancements` packets.
+#[derive(Clone, Debug, Default)]
+pub struct AdvancementTree {
+    advancements: HashMap<String, Advancement>,
+    /// When each achieved criterion of each advancement was achieved.
+    progress: HashMap<String, HashMap<String, i64>>,
+}
+
+impl AdvancementTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, packet: &Advancements) {
+        if packet.reset {
+            self.advancements.clear();
+            self.progress.clear();
+        }
+        for (id, advancement) in &packet.advancements {
+            self.advancements.insert(id.0.clone(), advancement.clone());
+        }
+        for id in &packet.removed {
+            self.advancements.remove(&id.0);
+            self.progress.remove(&id.0);
+        }
+        for (id, progress) in &packet.progress {
+            let achieved = self.progress.entry(id.0.clone()).or_default();
+            for (criterion, date) in &progress.criteria {
+                match date {
+                    Some(date) => achieved.insert(criterion.0.clone(), *date),
+                    None => achieved.remove(&criterion.0),
+                };
+            }
+        }
+    }
+
+    pub fn advancement(&self, id: &str) -> Option<&Advancement> {
+        self.advancements.get(id)
+    }
+
+    /// The advancements whose parent is `id`, sorted.
+    pub fn children(&self, id: &str) -> Vec<&str> {
+        let mut children: Vec<&str> = self
+            .advancements
+            .iter()
+            .filter(|(_, advancement)| advancement.parent.as_ref().is_some_and(|p| p.0 == id))
+            .map(|(child, _)| child.as_str())
+            .collect();
+        children.sort();
+        children
+    }
+
+    /// When a criterion of an advancement was achieved.
+    pub fn achieved(&self, id: &str, criterion: &str) -> Option<i64> {
+        self.progress.get(id)?.get(criterion).copied()
+    }
+
+    pub fn is_done(&self, id: &str) -> bool {
+        let Some(advancement) = self.advancements.get(id) else {
+            return false;
+        };
+        advancement.requirements.iter().all(|group| {
+            group
+                .iter()
+                .any(|criterion| self.achieved(id, criterion).is_some())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::limits::LimitError;
+    use crate::network::{ArrayError, Direction, Limits, Packet, State};
+
+    fn id(name: &str) -> Identifier {
+        Identifier(name.to_owned())
+    }
+
+    fn progress(criteria: &[(&str, Option<i64>)]) -> AdvancementProgress {
+        AdvancementProgress {
+            criteria: criteria
+                .iter()
+                .map(|&(criterion, date)| (id(criterion), date))
+                .collect(),
+        }
+    }
+
+    fn round_trip(packet: Advancements) -> Advancements {
+        let mut payload = vec![];
+        Packet::Advancements(packet)
+            .write_payload(&mut payload)
+            .unwrap();
+        let Packet::Advancements(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected Advancements");
+        };
+        read
+    }
+
+    #[test]
+    fn test_advancements() {
+        let root = Advancement {
+            display: Some(AdvancementDisplay {
+                title: Chat("\"Minecraft\"".to_owned()),
+                icon: Slot::Item {
+                    id: VarInt(1),
+                    count: 1,
+                    nbt: None,
+                },
+                flags: FLAG_BACKGROUND_TEXTURE | FLAG_SHOW_TOAST,
+                background_texture: Some(id(
+                    "minecraft:textures/gui/advancements/backgrounds/stone.png",
+                )),
+                ..AdvancementDisplay::default()
+            }),
+            criteria: vec![id("crafting_table")],
+            requirements: vec![vec!["crafting_table".to_owned()]],
+            ..Advancement::default()
+        };
+        let child = Advancement {
+            parent: Some(id("minecraft:story/root")),
+            criteria: vec![id("iron"), id("gold")],
+            requirements: vec![vec!["iron".to_owned()], vec!["gold".to_owned()]],
+            ..Advancement::default()
+        };
+        let packet = Advancements {
+            reset: true,
+            advancements: vec![
+                (id("minecraft:story/root"), root.clone()),
+                (id("minecraft:story/smelt_iron"), child.clone()),
+            ],
+            removed: vec![],
+            progress: vec![(
+                id("minecraft:story/smelt_iron"),
+                progress(&[("iron", Some(1_650_000_000_000)), ("gold", None)]),
+            )],
+        };
+        let read = round_trip(packet.clone());
+        assert_eq!(read.advancements, packet.advancements);
+        assert_eq!(read.progress, packet.progress);
+
+        let mut tree = AdvancementTree::new();
+        tree.apply(&read);
+        assert_eq!(
+            tree.children("minecraft:story/root"),
+            ["minecraft:story/smelt_iron"]
+        );
+        assert_eq!(
+            tree.achieved("minecraft:story/smelt_iron", "iron"),
+            Some(1_650_000_000_000)
+        );
+        assert!(!tree.is_done("minecraft:story/smelt_iron"));
+
+        tree.apply(&round_trip(Advancements {
+            progress: vec![(
+                id("minecraft:story/smelt_iron"),
+                progress(&[("gold", Some(1_650_000_001_000))]),
+            )],
+            ..Advancements::default()
+        }));
+        assert!(tree.is_done("minecraft:story/smelt_iron"));
+
+        tree.apply(&round_trip(Advancements {
+            removed: vec![id("minecraft:story/smelt_iron")],
+            ..Advancements::default()
+        }));
+        assert!(tree.advancement("minecraft:story/smelt_iron").is_none());
+        assert!(tree.children("minecraft:story/root").is_empty());
+    }
+
+    #[test]
+    fn test_progress_count() {
+        let negative = [0xff, 0xff, 0xff, 0xff, 0x0f];
+        let error = AdvancementProgress::minecraft_read(&mut &negative[..]).unwrap_err();
+        assert!(error.is::<ArrayError>());
+
+        let mut bytes = vec![];
+        progress(&[("a", None), ("b", None), ("c", None)])
+            .minecraft_write(&mut bytes)
+            .unwrap();
+        let limits = Limits {
+            max_array_length: 2,
+            ..Limits::DEFAULT
+        };
+        let error = limits
+            .scope(|| AdvancementProgress::minecraft_read(&mut &bytes[..]))
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<LimitError>().unwrap(),
+            LimitError::ArrayTooLong { length: 3, max: 2 }
+        );
+    }
+}
@@ -0,0 +1,773 @@
+use crate::network::language::{parse_template, Language, TemplatePart};
+use crate::network::Chat;
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// The character that starts a legacy formatting code, like `§c` for red.
+pub const LEGACY_CODE_PREFIX: char = '§';
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Debug)]
+pub enum ChatError {
+    EmptyArray,
+    UnknownContent(String),
+    InvalidColor(String),
+    InvalidEvent(String),
+}
+
+impl Display for ChatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for ChatError {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    /// A `#RRGGBB` color, which has no legacy code.
+    Rgb(u8, u8, u8),
+}
+
+/// The named colors with their JSON name, legacy code, RGB value and ANSI
+/// foreground code.
+const NAMED_COLORS: [(Color, &str, char, u32, u8); 16] = [
+    (Color::Black, "black", '0', 0x000000, 30),
+    (Color::DarkBlue, "dark_blue", '1', 0x0000AA, 34),
+    (Color::DarkGreen, "dark_green", '2', 0x00AA00, 32),
+    (Color::DarkAqua, "dark_aqua", '3', 0x00AAAA, 36),
+    (Color::DarkRed, "dark_red", '4', 0xAA0000, 31),
+    (Color::DarkPurple, "dark_purple", '5', 0xAA00AA, 35),
+    (Color::Gold, "gold", '6', 0xFFAA00, 33),
+    (Color::Gray, "gray", '7', 0xAAAAAA, 37),
+    (Color::DarkGray, "dark_gray", '8', 0x555555, 90),
+    (Color::Blue, "blue", '9', 0x5555FF, 94),
+    (Color::Green, "green", 'a', 0x55FF55, 92),
+    (Color::Aqua, "aqua", 'b', 0x55FFFF, 96),
+    (Color::Red, "red", 'c', 0xFF5555, 91),
+    (Color::LightPurple, "light_purple", 'd', 0xFF55FF, 95),
+    (Color::Yellow, "yellow", 'e', 0xFFFF55, 93),
+    (Color::White, "white", 'f', 0xFFFFFF, 97),
+];
+
+impl Color {
+    fn named(&self) -> Option<&'static (Color, &'static str, char, u32, u8)> {
+        NAMED_COLORS.iter().find(|(color, ..)| color == self)
+    }
+
+    /// Parses a color name or `#RRGGBB`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(hex) = name.strip_prefix('#') {
+            let rgb = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 6)?;
+            return Some(Self::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+        }
+        NAMED_COLORS
+            .iter()
+            .find(|(_, color_name, ..)| *color_name == name)
+            .map(|(color, ..)| *color)
+    }
+
+    pub fn name(&self) -> String {
+        match self.named() {
+            Some((_, name, ..)) => name.to_string(),
+            None => {
+                let (r, g, b) = self.rgb();
+                format!("#{r:02X}{g:02X}{b:02X}")
+            }
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        let code = code.to_ascii_lowercase();
+        NAMED_COLORS
+            .iter()
+            .find(|(_, _, color_code, ..)| *color_code == code)
+            .map(|(color, ..)| *color)
+    }
+
+    pub fn code(&self) -> Option<char> {
+        self.named().map(|(_, _, code, ..)| *code)
+    }
+
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match (self, self.named()) {
+            (Self::Rgb(r, g, b), _) => (*r, *g, *b),
+            (_, Some((_, _, _, rgb, _))) => ((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8),
+            (_, None) => unreachable!("every other color is named"),
+        }
+    }
+
+    /// The SGR parameters that set this as the foreground color.
+    fn ansi(&self) -> String {
+        match self.named() {
+            Some((_, _, _, _, ansi)) => ansi.to_string(),
+            None => {
+                let (r, g, b) = self.rgb();
+                format!("38;2;{r};{g};{b}")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClickEvent {
+    /// `open_url`, `run_command`, `suggest_command`, `change_page` or
+    /// `copy_to_clipboard`.
+    pub action: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HoverEvent {
+    Text(Box<ChatComponent>),
+    /// The item or entity, in the form the server sent it.
+    Item(Value),
+    Entity(Value),
+}
+
+/// Formatting that children inherit unless they set it themselves.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub font: Option<String>,
+    pub insertion: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    /// This style with anything it leaves unset taken from `parent`.
+    fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            color: self.color.or(parent.color),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            click_event: self
+                .click_event
+                .clone()
+                .or_else(|| parent.click_event.clone()),
+            hover_event: self
+                .hover_event
+                .clone()
+                .or_else(|| parent.hover_event.clone()),
+        }
+    }
+
+    /// The flags and their legacy codes and ANSI parameters.
+    fn decorations(&self) -> [(bool, char, &'static str); 5] {
+        [
+            (self.obfuscated == Some(true), 'k', "5"),
+            (self.bold == Some(true), 'l', "1"),
+            (self.strikethrough == Some(true), 'm', "9"),
+            (self.underlined == Some(true), 'n', "4"),
+            (self.italic == Some(true), 'o', "3"),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatContent {
+    Text(String),
+    /// A translation key, with the components substituted into it.
+    Translate {
+        key: String,
+        with: Vec<ChatComponent>,
+    },
+    /// The key bound to a control, like `key.jump`.
+    Keybind(String),
+    /// An entity's score. Servers fill in `value` before sending it.
+    Score {
+        name: String,
+        objective: String,
+        value: Option<String>,
+    },
+    /// An entity selector, like `@p`. Servers resolve it before sending it.
+    Selector(String),
+}
+
+impl Default for ChatContent {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+/// A piece of formatted chat text, with children that follow it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChatComponent {
+    pub content: ChatContent,
+    pub style: Style,
+    pub extra: Vec<ChatComponent>,
+}
+
+/// A run of text with the fully inherited style it is shown in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+fn bool_field(object: &Map<String, Value>, name: &str) -> Option<bool> {
+    object.get(name).and_then(Value::as_bool)
+}
+
+fn string_field(object: &Map<String, Value>, name: &str) -> Option<String> {
+    object.get(name).and_then(Value::as_str).map(str::to_owned)
+}
+
+/// Strings, numbers and booleans are all text in chat JSON.
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+impl ChatComponent {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: ChatContent::Text(text.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn translate(key: impl Into<String>, with: Vec<ChatComponent>) -> Self {
+        Self {
+            content: ChatContent::Translate {
+                key: key.into(),
+                with,
+            },
+            ..Self::default()
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::from_value(&serde_json::from_str(json)?)
+    }
+
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let object = match value {
+            Value::Object(object) => object,
+            // The first element is the parent of the rest.
+            Value::Array(array) => {
+                let (first, rest) = array.split_first().ok_or(ChatError::EmptyArray)?;
+                let mut component = Self::from_value(first)?;
+                for child in rest {
+                    component.extra.push(Self::from_value(child)?);
+                }
+                return Ok(component);
+            }
+            value => match value_text(value) {
+                Some(text) => return Ok(Self::text(text)),
+                None => Err(ChatError::UnknownContent(value.to_string()))?,
+            },
+        };
+
+        let content = if let Some(text) = object.get("text") {
+            ChatContent::Text(value_text(text).unwrap_or_default())
+        } else if let Some(key) = object.get("translate").and_then(value_text) {
+            let with = match object.get("with") {
+                Some(Value::Array(with)) => {
+                    with.iter().map(Self::from_value).collect::<Result<_>>()?
+                }
+                _ => vec![],
+            };
+            ChatContent::Translate { key, with }
+        } else if let Some(Value::Object(score)) = object.get("score") {
+            ChatContent::Score {
+                name: string_field(score, "name").unwrap_or_default(),
+                objective: string_field(score, "objective").unwrap_or_default(),
+                value: score.get("value").and_then(value_text),
+            }
+        } else if let Some(selector) = string_field(object, "selector") {
+            ChatContent::Selector(selector)
+        } else if let Some(keybind) = string_field(object, "keybind") {
+            ChatContent::Keybind(keybind)
+        } else {
+            Err(ChatError::UnknownContent(value.to_string()))?
+        };
+
+        let extra = match object.get("extra") {
+            Some(Value::Array(extra)) => {
+                extra.iter().map(Self::from_value).collect::<Result<_>>()?
+            }
+            _ => vec![],
+        };
+
+        Ok(Self {
+            content,
+            style: Self::style_from_object(object)?,
+            extra,
+        })
+    }
+
+    fn style_from_object(object: &Map<String, Value>) -> Result<Style> {
+        let color = match object.get("color").and_then(Value::as_str) {
+            None | Some("reset") => None,
+            Some(name) => {
+                Some(Color::from_name(name).ok_or_else(|| ChatError::InvalidColor(name.into()))?)
+            }
+        };
+        let click_event = match object.get("clickEvent") {
+            Some(Value::Object(event)) => Some(ClickEvent {
+                action: string_field(event, "action").ok_or_else(|| {
+                    ChatError::InvalidEvent(Value::from(event.clone()).to_string())
+                })?,
+                value: event.get("value").and_then(value_text).unwrap_or_default(),
+            }),
+            _ => None,
+        };
+        let hover_event = match object.get("hoverEvent") {
+            Some(Value::Object(event)) => {
+                let invalid = || ChatError::InvalidEvent(Value::from(event.clone()).to_string());
+                // Before 1.16 the contents were sent as `value`.
+                let contents = event
+                    .get("contents")
+                    .or_else(|| event.get("value"))
+                    .ok_or_else(invalid)?;
+                Some(match event.get("action").and_then(Value::as_str) {
+                    Some("show_text") => HoverEvent::Text(Box::new(Self::from_value(contents)?)),
+                    Some("show_item") => HoverEvent::Item(contents.clone()),
+                    Some("show_entity") => HoverEvent::Entity(contents.clone()),
+                    _ => Err(invalid())?,
+                })
+            }
+            _ => None,
+        };
+        Ok(Style {
+            color,
+            bold: bool_field(object, "bold"),
+            italic: bool_field(object, "italic"),
+            underlined: bool_field(object, "underlined"),
+            strikethrough: bool_field(object, "strikethrough"),
+            obfuscated: bool_field(object, "obfuscated"),
+            font: string_field(object, "font"),
+            insertion: string_field(object, "insertion"),
+            click_event,
+            hover_event,
+        })
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut object = Map::new();
+        match &self.content {
+            ChatContent::Text(text) => {
+                object.insert("text".into(), text.clone().into());
+            }
+            ChatContent::Translate { key, with } => {
+                object.insert("translate".into(), key.clone().into());
+                if !with.is_empty() {
+                    object.insert("with".into(), with.iter().map(Self::to_value).collect());
+                }
+            }
+            ChatContent::Keybind(keybind) => {
+                object.insert("keybind".into(), keybind.clone().into());
+            }
+            ChatContent::Score {
+                name,
+                objective,
+                value,
+            } => {
+                let mut score = Map::new();
+                score.insert("name".into(), name.clone().into());
+                score.insert("objective".into(), objective.clone().into());
+                if let Some(value) = value {
+                    score.insert("value".into(), value.clone().into());
+                }
+                object.insert("score".into(), score.into());
+            }
+            ChatContent::Selector(selector) => {
+                object.insert("selector".into(), selector.clone().into());
+            }
+        }
+
+        let style = &self.style;
+        if let Some(color) = style.color {
+            object.insert("color".into(), color.name().into());
+        }
+        let flags = [
+            ("bold", style.bold),
+            ("italic", style.italic),
+            ("underlined", style.underlined),
+            ("strikethrough", style.strikethrough),
+            ("obfuscated", style.obfuscated),
+        ];
+        for (name, flag) in flags {
+            if let Some(flag) = flag {
+                object.insert(name.into(), flag.into());
+            }
+        }
+        if let Some(font) = &style.font {
+            object.insert("font".into(), font.clone().into());
+        }
+        if let Some(insertion) = &style.insertion {
+            object.insert("insertion".into(), insertion.clone().into());
+        }
+        if let Some(event) = &style.click_event {
+            let mut click = Map::new();
+            click.insert("action".into(), event.action.clone().into());
+            click.insert("value".into(), event.value.clone().into());
+            object.insert("clickEvent".into(), click.into());
+        }
+        if let Some(event) = &style.hover_event {
+            let (action, contents) = match event {
+                HoverEvent::Text(text) => ("show_text", text.to_value()),
+                HoverEvent::Item(item) => ("show_item", item.clone()),
+                HoverEvent::Entity(entity) => ("show_entity", entity.clone()),
+            };
+            let mut hover = Map::new();
+            hover.insert("action".into(), action.into());
+            hover.insert("contents".into(), contents);
+            object.insert("hoverEvent".into(), hover.into());
+        }
+
+        if !self.extra.is_empty() {
+            object.insert(
+                "extra".into(),
+                self.extra.iter().map(Self::to_value).collect(),
+            );
+        }
+        object.into()
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    /// Converts text with `§` formatting codes. A color code resets the
+    /// formatting before it, as in vanilla.
+    pub fn from_legacy(text: &str) -> Self {
+        let mut root = Self::text("");
+        let mut style = Style::default();
+        let mut current = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != LEGACY_CODE_PREFIX {
+                current.push(c);
+                continue;
+            }
+            let Some(code) = chars.next() else {
+                break;
+            };
+            if !current.is_empty() {
+                root.extra.push(Self {
+                    content: ChatContent::Text(std::mem::take(&mut current)),
+                    style: style.clone(),
+                    extra: vec![],
+                });
+            }
+            match code.to_ascii_lowercase() {
+                'k' => style.obfuscated = Some(true),
+                'l' => style.bold = Some(true),
+                'm' => style.strikethrough = Some(true),
+                'n' => style.underlined = Some(true),
+                'o' => style.italic = Some(true),
+                'r' => style = Style::default(),
+                code => {
+                    if let Some(color) = Color::from_code(code) {
+                        style = Style {
+                            color: Some(color),
+                            ..Style::default()
+                        };
+                    }
+                }
+            }
+        }
+        if !current.is_empty() {
+            root.extra.push(Self {
+                content: ChatContent::Text(current),
+                style,
+                extra: vec![],
+            });
+        }
+        // Plain text needs no wrapper.
+        if root.extra.len() == 1 && root.extra[0].style == Style::default() {
+            return root.extra.pop().unwrap();
+        }
+        root
+    }
+
+    /// The text of this component and its children with their styles fully
+    /// inherited. Translations show their key.
+    pub fn spans(&self) -> Vec<Span> {
+        self.spans_in(&Language::default())
+    }
+
+    /// The spans with translations looked up in `language`, falling back to
+    /// the key.
+    pub fn spans_in(&self, language: &Language) -> Vec<Span> {
+        let mut spans = vec![];
+        self.collect_spans(language, &Style::default(), &mut spans);
+        spans
+    }
+
+    fn collect_spans(&self, language: &Language, parent: &Style, spans: &mut Vec<Span>) {
+        let style = self.style.inherit(parent);
+        let span = |text: &str| Span {
+            text: text.to_owned(),
+            style: style.clone(),
+        };
+        match &self.content {
+            ChatContent::Text(text) => spans.push(span(text)),
+            ChatContent::Translate { key, with } => {
+                for part in parse_template(language.template(key)) {
+                    match part {
+                        TemplatePart::Literal(literal) => spans.push(span(literal)),
+                        TemplatePart::Argument(index) => match with.get(index) {
+                            Some(argument) => argument.collect_spans(language, &style, spans),
+                            None => spans.push(span(&format!("%{}$s", index + 1))),
+                        },
+                    }
+                }
+            }
+            ChatContent::Keybind(keybind) => spans.push(span(keybind)),
+            ChatContent::Score { value, .. } => {
+                spans.push(span(value.as_deref().unwrap_or_default()))
+            }
+            ChatContent::Selector(selector) => spans.push(span(selector)),
+        }
+        for child in &self.extra {
+            child.collect_spans(language, &style, spans);
+        }
+    }
+
+    pub fn to_plain(&self) -> String {
+        self.to_plain_in(&Language::default())
+    }
+
+    pub fn to_plain_in(&self, language: &Language) -> String {
+        self.spans_in(language)
+            .into_iter()
+            .map(|span| span.text)
+            .collect()
+    }
+
+    /// The text with `§` codes. RGB colors have no code and are dropped.
+    pub fn to_legacy(&self) -> String {
+        self.to_legacy_in(&Language::default())
+    }
+
+    pub fn to_legacy_in(&self, language: &Language) -> String {
+        let mut legacy = String::new();
+        let mut previous = Style::default();
+        for span in self
+            .spans_in(language)
+            .into_iter()
+            .filter(|span| !span.text.is_empty())
+        {
+            let style = span.style;
+            if (style.color, style.decorations()) != (previous.color, previous.decorations()) {
+                match style.color.and_then(|color| color.code()) {
+                    Some(code) => legacy.extend([LEGACY_CODE_PREFIX, code]),
+                    None if previous != Style::default() => {
+                        legacy.extend([LEGACY_CODE_PREFIX, 'r'])
+                    }
+                    None => {}
+                }
+                for (_, code, _) in style.decorations().into_iter().filter(|(on, ..)| *on) {
+                    legacy.extend([LEGACY_CODE_PREFIX, code]);
+                }
+            }
+            legacy.push_str(&span.text);
+            previous = style;
+        }
+        legacy
+    }
+
+    /// The text with ANSI escape sequences for a terminal.
+    pub fn to_ansi(&self) -> String {
+        self.to_ansi_in(&Language::default())
+    }
+
+    pub fn to_ansi_in(&self, language: &Language) -> String {
+        let mut ansi = String::new();
+        for span in self
+            .spans_in(language)
+            .into_iter()
+            .filter(|span| !span.text.is_empty())
+        {
+            let style = span.style;
+            let mut parameters: Vec<String> = style.color.iter().map(Color::ansi).collect();
+            for (_, _, parameter) in style.decorations().into_iter().filter(|(on, ..)| *on) {
+                parameters.push(parameter.to_owned());
+            }
+            if parameters.is_empty() {
+                ansi.push_str(&span.text);
+            } else {
+                ansi.push_str(&format!(
+                    "\x1b[{}m{}{ANSI_RESET}",
+                    parameters.join(";"),
+                    span.text
+                ));
+            }
+        }
+        ansi
+    }
+}
+
+impl Chat {
+    pub fn component(&self) -> Result<ChatComponent> {
+        ChatComponent::from_json(&self.0)
+    }
+
+    /// The plain text of the message, or the raw string if it is not valid
+    /// chat JSON.
+    pub fn to_plain_in(&self, language: &Language) -> String {
+        match self.component() {
+            Ok(component) => component.to_plain_in(language),
+            Err(_) => self.0.clone(),
+        }
+    }
+}
+
+impl From<&ChatComponent> for Chat {
+    fn from(component: &ChatComponent) -> Self {
+        Chat(component.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_serialize() {
+        let json = json!({
+            "translate": "chat.type.text",
+            "with": [
+                {
+                    "text": "Notch",
+                    "clickEvent": { "action": "suggest_command", "value": "/msg Notch " },
+                    "hoverEvent": { "action": "show_text", "contents": "Click to message" },
+                    "insertion": "Notch"
+                },
+                "hello"
+            ],
+            "color": "#FF8800",
+            "extra": [{ "keybind": "key.jump", "bold": true }, { "score": { "name": "Notch", "objective": "kills", "value": "3" } }]
+        });
+        let component = ChatComponent::from_value(&json).unwrap();
+        let ChatContent::Translate { key, with } = &component.content else {
+            panic!("expected a translation");
+        };
+        assert_eq!(key, "chat.type.text");
+        assert_eq!(with[1], ChatComponent::text("hello"));
+        assert_eq!(
+            with[0].style.click_event,
+            Some(ClickEvent {
+                action: "suggest_command".to_owned(),
+                value: "/msg Notch ".to_owned(),
+            })
+        );
+        assert_eq!(
+            with[0].style.hover_event,
+            Some(HoverEvent::Text(Box::new(ChatComponent::text(
+                "Click to message"
+            ))))
+        );
+        assert_eq!(component.style.color, Some(Color::Rgb(0xFF, 0x88, 0x00)));
+        assert_eq!(
+            component.extra[0].content,
+            ChatContent::Keybind("key.jump".to_owned())
+        );
+        assert_eq!(component.extra[0].style.bold, Some(true));
+
+        // Serializing and parsing again gives the same tree.
+        let reparsed = ChatComponent::from_json(&component.to_json()).unwrap();
+        assert_eq!(reparsed, component);
+        assert_eq!(component.to_value()["color"], "#FF8800");
+        assert_eq!(component.to_value()["with"][1], json!({ "text": "hello" }));
+    }
+
+    #[test]
+    fn test_parse_shorthand() {
+        assert_eq!(
+            ChatComponent::from_json(r#""plain""#).unwrap(),
+            ChatComponent::text("plain")
+        );
+        let component =
+            ChatComponent::from_json(r#"["a", {"text": "b", "color": "red"}, 3]"#).unwrap();
+        assert_eq!(component.to_plain(), "ab3");
+        assert_eq!(component.extra[0].style.color, Some(Color::Red));
+        assert!(ChatComponent::from_json("[]").is_err());
+        assert!(ChatComponent::from_json(r#"{"text": "x", "color": "mauve"}"#).is_err());
+        assert_eq!(
+            Chat(r#"{"text":"Server is full"}"#.to_owned())
+                .component()
+                .unwrap()
+                .to_plain(),
+            "Server is full"
+        );
+    }
+
+    #[test]
+    fn test_legacy() {
+        let component = ChatComponent::from_legacy("§6§lGold §rplain §cred");
+        assert_eq!(component.to_plain(), "Gold plain red");
+        assert_eq!(component.extra[0].style.color, Some(Color::Gold));
+        assert_eq!(component.extra[0].style.bold, Some(true));
+        assert_eq!(component.extra[1].style, Style::default());
+        assert_eq!(component.extra[2].style.color, Some(Color::Red));
+        assert_eq!(component.to_legacy(), "§6§lGold §rplain §cred");
+
+        assert_eq!(
+            ChatComponent::from_legacy("no codes"),
+            ChatComponent::text("no codes")
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let mut component = ChatComponent::text("Hi ");
+        component.style.color = Some(Color::Green);
+        let mut child = ChatComponent::text("there");
+        child.style.italic = Some(true);
+        component.extra.push(child);
+        component.extra.push(ChatComponent::translate(
+            "multiplayer.player.joined",
+            vec![],
+        ));
+
+        assert_eq!(component.to_plain(), "Hi theremultiplayer.player.joined");
+        assert_eq!(
+            component.to_ansi(),
+            "\x1b[92mHi \x1b[0m\x1b[92;3mthere\x1b[0m\x1b[92mmultiplayer.player.joined\x1b[0m"
+        );
+
+        component.style.color = Some(Color::Rgb(1, 2, 3));
+        assert!(component.to_ansi().starts_with("\x1b[38;2;1;2;3mHi "));
+    }
+}
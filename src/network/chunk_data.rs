@@ -0,0 +1,398 @@
+use crate::network::packets::{BlockChange, ChunkDataAndUpdateLight, MultiBlockChange};
+use crate::network::{MinecraftIo, Position, VarInt, VarLong};
+use anyhow::Result;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+
+/// Blocks along each edge of a section.
+pub const SECTION_WIDTH: usize = 16;
+pub const BLOCKS_PER_SECTION: usize = SECTION_WIDTH * SECTION_WIDTH * SECTION_WIDTH;
+/// Biomes are stored per 4×4×4 cell, so there are 4 along each edge.
+pub const BIOME_WIDTH: usize = 4;
+pub const BIOMES_PER_SECTION: usize = BIOME_WIDTH * BIOME_WIDTH * BIOME_WIDTH;
+
+#[derive(Copy, Clone, Debug)]
+pub enum ChunkDataError {
+    InvalidBitsPerEntry(u8),
+    DataLengthMismatch { expected: usize, actual: usize },
+    PaletteIndexOutOfRange { index: u64, palette_length: usize },
+}
+
+impl Display for ChunkDataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for ChunkDataError {}
+
+/// The two kinds of paletted container, which differ in how many entries they
+/// hold and which bit widths mean which palette.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ContainerKind {
+    BlockStates,
+    Biomes,
+}
+
+impl ContainerKind {
+    fn entries(self) -> usize {
+        match self {
+            Self::BlockStates => BLOCKS_PER_SECTION,
+            Self::Biomes => BIOMES_PER_SECTION,
+        }
+    }
+
+    /// Widths up to this use an indirect palette; wider ones index the
+    /// global registry directly.
+    fn max_indirect_bits(self) -> u8 {
+        match self {
+            Self::BlockStates => 8,
+            Self::Biomes => 3,
+        }
+    }
+
+    /// The width actually used for an indirect palette. Block states are
+    /// never packed narrower than 4 bits.
+    fn indirect_bits(self, bits: u8) -> u8 {
+        match self {
+            Self::BlockStates => bits.max(4),
+            Self::Biomes => bits,
+        }
+    }
+}
+
+/// Reads a paletted container and returns the global ID of every entry, in
+/// y, z, x order.
+fn read_paletted_container(reader: &mut impl Read, kind: ContainerKind) -> Result<Vec<u32>> {
+    let bits = u8::minecraft_read(reader)?;
+    let entries = kind.entries();
+
+    if bits == 0 {
+        let value = VarInt::read(reader)?.0 as u32;
+        // The data array is still sent, always empty.
+        let data = <Vec<u64>>::minecraft_read(reader)?;
+        if !data.is_empty() {
+            Err(ChunkDataError::DataLengthMismatch {
+                expected: 0,
+                actual: data.len(),
+            })?;
+        }
+        return Ok(vec![value; entries]);
+    }
+    if bits > 32 {
+        Err(ChunkDataError::InvalidBitsPerEntry(bits))?;
+    }
+
+    let (palette, bits) = if bits <= kind.max_indirect_bits() {
+        let palette = <Vec<VarInt>>::minecraft_read(reader)?;
+        let palette: Vec<u32> = palette.into_iter().map(|id| id.0 as u32).collect();
+        (Some(palette), kind.indirect_bits(bits))
+    } else {
+        (None, bits)
+    };
+
+    let data = <Vec<u64>>::minecraft_read(reader)?;
+    let values = unpack(&data, bits, entries)?;
+    match palette {
+        None => Ok(values.into_iter().map(|value| value as u32).collect()),
+        Some(palette) => values
+            .into_iter()
+            .map(|index| {
+                palette.get(index as usize).copied().ok_or_else(|| {
+                    ChunkDataError::PaletteIndexOutOfRange {
+                        index,
+                        palette_length: palette.len(),
+                    }
+                    .into()
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Unpacks `entries` values of `bits` bits each from longs, lowest bits
+/// first. Values never span two longs; leftover high bits are padding.
+fn unpack(data: &[u64], bits: u8, entries: usize) -> Result<Vec<u64>> {
+    let per_long = 64 / bits as usize;
+    let expected = entries.div_ceil(per_long);
+    if data.len() != expected {
+        Err(ChunkDataError::DataLengthMismatch {
+            expected,
+            actual: data.len(),
+        })?;
+    }
+
+    let mask = (1u64 << bits) - 1;
+    Ok((0..entries)
+        .map(|i| data[i / per_long] >> (i % per_long * bits as usize) & mask)
+        .collect())
+}
+
+/// One 16×16×16 section of a chunk column as sent in `ChunkDataAndUpdateLight`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkSection {
+    /// The number of non-air blocks, as counted by the server.
+    pub block_count: i16,
+    /// Global block state IDs, indexed by `y * 256 + z * 16 + x`.
+    pub block_states: Vec<u32>,
+    /// Biome registry IDs, indexed by `y * 16 + z * 4 + x` in 4 block cells.
+    pub biomes: Vec<u32>,
+}
+
+impl ChunkSection {
+    pub fn read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            block_count: i16::minecraft_read(reader)?,
+            block_states: read_paletted_container(reader, ContainerKind::BlockStates)?,
+            biomes: read_paletted_container(reader, ContainerKind::Biomes)?,
+        })
+    }
+
+    /// The block state at a position within the section.
+    pub fn block_state(&self, x: usize, y: usize, z: usize) -> u32 {
+        assert!(x < SECTION_WIDTH && y < SECTION_WIDTH && z < SECTION_WIDTH);
+        self.block_states[(y * SECTION_WIDTH + z) * SECTION_WIDTH + x]
+    }
+
+    /// The biome at a block position within the section.
+    pub fn biome(&self, x: usize, y: usize, z: usize) -> u32 {
+        assert!(x < SECTION_WIDTH && y < SECTION_WIDTH && z < SECTION_WIDTH);
+        let (x, y, z) = (x / 4, y / 4, z / 4);
+        self.biomes[(y * BIOME_WIDTH + z) * BIOME_WIDTH + x]
+    }
+}
+
+/// Decodes every section in a chunk column's data, bottom section first.
+pub fn read_chunk_sections(data: &[u8]) -> Result<Vec<ChunkSection>> {
+    let mut reader = data;
+    let mut sections = vec![];
+    while !reader.is_empty() {
+        sections.push(ChunkSection::read(&mut reader)?);
+    }
+    Ok(sections)
+}
+
+impl ChunkDataAndUpdateLight {
+    pub fn sections(&self) -> Result<Vec<ChunkSection>> {
+        read_chunk_sections(&self.data)
+    }
+}
+
+/// Unpacks the section coordinates of `MultiBlockChange`: 22 bits of x, 22 of
+/// z, then 20 of y, each signed.
+pub fn decode_section_position(position: i64) -> (i32, i32, i32) {
+    let x = (position >> 42) as i32;
+    let y = (position << 44 >> 44) as i32;
+    let z = (position << 22 >> 42) as i32;
+    (x, y, z)
+}
+
+pub fn encode_section_position((x, y, z): (i32, i32, i32)) -> i64 {
+    ((x as i64) & 0x3f_ffff) << 42 | ((z as i64) & 0x3f_ffff) << 20 | (y as i64) & 0xf_ffff
+}
+
+/// Unpacks one `MultiBlockChange` entry into the block state and the block's
+/// x, y, z within the section.
+pub fn decode_block_entry(entry: VarLong) -> (u32, (usize, usize, usize)) {
+    let entry = entry.0 as u64;
+    let state = (entry >> 12) as u32;
+    let x = (entry >> 8 & 0xf) as usize;
+    let z = (entry >> 4 & 0xf) as usize;
+    let y = (entry & 0xf) as usize;
+    (state, (x, y, z))
+}
+
+pub fn encode_block_entry(state: u32, (x, y, z): (usize, usize, usize)) -> VarLong {
+    VarLong((state as i64) << 12 | (x << 8 | z << 4 | y) as i64)
+}
+
+impl BlockChange {
+    pub fn block_state(&self) -> u32 {
+        self.block_id.0 as u32
+    }
+}
+
+impl MultiBlockChange {
+    pub fn section_position(&self) -> (i32, i32, i32) {
+        decode_section_position(self.chunk_section_position)
+    }
+
+    /// The changed blocks as world positions and their new block states.
+    pub fn changes(&self) -> impl Iterator<Item = (Position, u32)> + '_ {
+        let (section_x, section_y, section_z) = self.section_position();
+        let width = SECTION_WIDTH as i32;
+        self.blocks.iter().map(move |&entry| {
+            let (state, (x, y, z)) = decode_block_entry(entry);
+            let position = Position {
+                x: section_x * width + x as i32,
+                y: section_y * width + y as i32,
+                z: section_z * width + z as i32,
+            };
+            (position, state)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs values the way a server does, for building test input.
+    fn pack(values: &[u64], bits: usize) -> Vec<u64> {
+        let per_long = 64 / bits;
+        let mut data = vec![0u64; values.len().div_ceil(per_long)];
+        for (i, value) in values.iter().enumerate() {
+            data[i / per_long] |= value << (i % per_long * bits);
+        }
+        data
+    }
+
+    fn write_container(out: &mut Vec<u8>, bits: u8, palette: Option<&[i32]>, data: &[u64]) {
+        bits.minecraft_write(out).unwrap();
+        if let Some(palette) = palette {
+            let palette: Vec<VarInt> = palette.iter().copied().map(VarInt).collect();
+            palette.minecraft_write(out).unwrap();
+        }
+        data.to_vec().minecraft_write(out).unwrap();
+    }
+
+    fn write_single(out: &mut Vec<u8>, value: i32) {
+        0u8.minecraft_write(out).unwrap();
+        VarInt(value).minecraft_write(out).unwrap();
+        VarInt(0).minecraft_write(out).unwrap();
+    }
+
+    #[test]
+    fn test_single_valued_section() {
+        let mut data = vec![];
+        0i16.minecraft_write(&mut data).unwrap();
+        write_single(&mut data, 0);
+        write_single(&mut data, 1);
+
+        let sections = read_chunk_sections(&data).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].block_count, 0);
+        assert!(sections[0].block_states.iter().all(|&state| state == 0));
+        assert_eq!(sections[0].biome(15, 15, 15), 1);
+    }
+
+    #[test]
+    fn test_indirect_section() {
+        // Air below y = 4 is stone, with a torch at (3, 5, 7).
+        let palette = [0, 1, 4008];
+        let indices: Vec<u64> = (0..BLOCKS_PER_SECTION)
+            .map(|i| match (i % 16, i / 256, i / 16 % 16) {
+                (3, 5, 7) => 2,
+                (_, y, _) if y < 4 => 1,
+                _ => 0,
+            })
+            .collect();
+        let biomes: Vec<u64> = (0..BIOMES_PER_SECTION as u64).map(|i| i % 2).collect();
+
+        let mut data = vec![];
+        1025i16.minecraft_write(&mut data).unwrap();
+        // Sent as 2 bits, but block states are always packed as at least 4.
+        write_container(&mut data, 2, Some(&palette), &pack(&indices, 4));
+        write_container(&mut data, 1, Some(&[7, 12]), &pack(&biomes, 1));
+
+        let section = &read_chunk_sections(&data).unwrap()[0];
+        assert_eq!(section.block_count, 1025);
+        assert_eq!(section.block_state(0, 0, 0), 1);
+        assert_eq!(section.block_state(15, 3, 15), 1);
+        assert_eq!(section.block_state(0, 4, 0), 0);
+        assert_eq!(section.block_state(3, 5, 7), 4008);
+        assert_eq!(section.biome(0, 0, 0), 7);
+        assert_eq!(section.biome(4, 0, 0), 12);
+    }
+
+    #[test]
+    fn test_direct_section() {
+        // 15 bits leaves 4 padding bits at the top of each long.
+        let states: Vec<u64> = (0..BLOCKS_PER_SECTION as u64).map(|i| i * 5).collect();
+        let biomes: Vec<u64> = (0..BIOMES_PER_SECTION as u64).collect();
+
+        let mut data = vec![];
+        4096i16.minecraft_write(&mut data).unwrap();
+        write_container(&mut data, 15, None, &pack(&states, 15));
+        write_container(&mut data, 6, None, &pack(&biomes, 6));
+        0i16.minecraft_write(&mut data).unwrap();
+        write_single(&mut data, 0);
+        write_single(&mut data, 0);
+
+        let sections = read_chunk_sections(&data).unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].block_state(1, 0, 0), 5);
+        assert_eq!(sections[0].block_state(0, 1, 0), 256 * 5);
+        assert_eq!(sections[0].block_state(15, 15, 15), 4095 * 5);
+        assert_eq!(sections[0].biome(15, 15, 15), 63);
+    }
+
+    #[test]
+    fn test_invalid_sections() {
+        let mut data = vec![];
+        0i16.minecraft_write(&mut data).unwrap();
+        write_container(&mut data, 4, Some(&[0]), &[0; 10]);
+        write_single(&mut data, 0);
+        assert!(read_chunk_sections(&data).is_err());
+
+        let mut data = vec![];
+        0i16.minecraft_write(&mut data).unwrap();
+        write_container(&mut data, 4, Some(&[0]), &pack(&[1; BLOCKS_PER_SECTION], 4));
+        write_single(&mut data, 0);
+        assert!(read_chunk_sections(&data).is_err());
+
+        // Truncated after the block states.
+        let mut data = vec![];
+        0i16.minecraft_write(&mut data).unwrap();
+        write_single(&mut data, 0);
+        assert!(read_chunk_sections(&data).is_err());
+    }
+
+    #[test]
+    fn test_multi_block_change() {
+        for position in [(0, 0, 0), (-1, -4, 2), (1875000, 19, -1875000)] {
+            let packed = encode_section_position(position);
+            assert_eq!(decode_section_position(packed), position);
+        }
+        // x = 1, z = 2, y = -3, as a server writes it.
+        assert_eq!(
+            decode_section_position(1 << 42 | 2 << 20 | 0xf_fffd),
+            (1, -3, 2)
+        );
+
+        let entry = encode_block_entry(20341, (15, 0, 7));
+        assert_eq!(decode_block_entry(entry), (20341, (15, 0, 7)));
+
+        let packet = MultiBlockChange {
+            chunk_section_position: encode_section_position((-1, -4, 2)),
+            trust_light_edges: false,
+            blocks: vec![
+                encode_block_entry(1, (0, 0, 0)),
+                encode_block_entry(9, (15, 15, 3)),
+            ],
+        };
+        let changes: Vec<_> = packet.changes().collect();
+        assert_eq!(
+            changes,
+            [
+                (
+                    Position {
+                        x: -16,
+                        y: -64,
+                        z: 32
+                    },
+                    1
+                ),
+                (
+                    Position {
+                        x: -1,
+                        y: -49,
+                        z: 35
+                    },
+                    9
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,877 @@
+use crate::network::packets::DeclareCommands;
+use crate::network::{Identifier, MinecraftIo, VarInt};
+use anyhow::Result;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+const NODE_TYPE_MASK: u8 = 0x03;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_REDIRECT: u8 = 0x08;
+const FLAG_SUGGESTIONS: u8 = 0x10;
+
+const NUMBER_HAS_MIN: u8 = 0x01;
+const NUMBER_HAS_MAX: u8 = 0x02;
+
+const ENTITY_SINGLE: u8 = 0x01;
+const ENTITY_PLAYERS_ONLY: u8 = 0x02;
+
+const SCORE_HOLDER_MULTIPLE: u8 = 0x01;
+
+/// Parsers that are sent without any properties.
+const PARSERS_WITHOUT_PROPERTIES: &[&str] = &[
+    "brigadier:bool",
+    "minecraft:game_profile",
+    "minecraft:block_pos",
+    "minecraft:column_pos",
+    "minecraft:vec3",
+    "minecraft:vec2",
+    "minecraft:block_state",
+    "minecraft:block_predicate",
+    "minecraft:item_stack",
+    "minecraft:item_predicate",
+    "minecraft:color",
+    "minecraft:component",
+    "minecraft:message",
+    "minecraft:nbt_compound_tag",
+    "minecraft:nbt_tag",
+    "minecraft:nbt_path",
+    "minecraft:objective",
+    "minecraft:objective_criteria",
+    "minecraft:operation",
+    "minecraft:particle",
+    "minecraft:angle",
+    "minecraft:rotation",
+    "minecraft:scoreboard_slot",
+    "minecraft:swizzle",
+    "minecraft:team",
+    "minecraft:item_slot",
+    "minecraft:resource_location",
+    "minecraft:mob_effect",
+    "minecraft:function",
+    "minecraft:entity_anchor",
+    "minecraft:int_range",
+    "minecraft:float_range",
+    "minecraft:item_enchantment",
+    "minecraft:entity_summon",
+    "minecraft:dimension",
+    "minecraft:time",
+    "minecraft:uuid",
+];
+
+#[derive(Clone, Debug)]
+pub enum CommandError {
+    InvalidNodeType(u8),
+    UnknownParser(String),
+    InvalidStringKind(i32),
+    NodeOutOfRange { node: i32, count: usize },
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for CommandError {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StringKind {
+    SingleWord,
+    QuotablePhrase,
+    GreedyPhrase,
+}
+
+/// How an argument node parses its part of the command, with the parser's
+/// properties.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgumentParser {
+    Bool,
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Float {
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Long {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    String(StringKind),
+    Entity {
+        single: bool,
+        players_only: bool,
+    },
+    ScoreHolder {
+        allow_multiple: bool,
+    },
+    Range {
+        decimals: bool,
+    },
+    Resource {
+        registry: Identifier,
+    },
+    ResourceOrTag {
+        registry: Identifier,
+    },
+    /// One of the parsers that has no properties.
+    Other(Identifier),
+}
+
+fn read_bounds<T: MinecraftIo>(reader: &mut impl Read) -> Result<(Option<T>, Option<T>)> {
+    let flags = u8::minecraft_read(reader)?;
+    let min = match flags & NUMBER_HAS_MIN {
+        0 => None,
+        _ => Some(T::minecraft_read(reader)?),
+    };
+    let max = match flags & NUMBER_HAS_MAX {
+        0 => None,
+        _ => Some(T::minecraft_read(reader)?),
+    };
+    Ok((min, max))
+}
+
+fn write_bounds<T: MinecraftIo>(
+    min: &Option<T>,
+    max: &Option<T>,
+    writer: &mut impl Write,
+) -> Result<()> {
+    let mut flags = 0;
+    if min.is_some() {
+        flags |= NUMBER_HAS_MIN;
+    }
+    if max.is_some() {
+        flags |= NUMBER_HAS_MAX;
+    }
+    flags.minecraft_write(writer)?;
+    min.minecraft_write(writer)?;
+    max.minecraft_write(writer)
+}
+
+impl ArgumentParser {
+    pub fn identifier(&self) -> &str {
+        match self {
+            Self::Bool => "brigadier:bool",
+            Self::Double { .. } => "brigadier:double",
+            Self::Float { .. } => "brigadier:float",
+            Self::Integer { .. } => "brigadier:integer",
+            Self::Long { .. } => "brigadier:long",
+            Self::String(_) => "brigadier:string",
+            Self::Entity { .. } => "minecraft:entity",
+            Self::ScoreHolder { .. } => "minecraft:score_holder",
+            Self::Range { .. } => "minecraft:range",
+            Self::Resource { .. } => "minecraft:resource",
+            Self::ResourceOrTag { .. } => "minecraft:resource_or_tag",
+            Self::Other(identifier) => &identifier.0,
+        }
+    }
+
+    fn read(identifier: Identifier, reader: &mut impl Read) -> Result<Self> {
+        Ok(match identifier.0.as_str() {
+            "brigadier:bool" => Self::Bool,
+            "brigadier:double" => {
+                let (min, max) = read_bounds(reader)?;
+                Self::Double { min, max }
+            }
+            "brigadier:float" => {
+                let (min, max) = read_bounds(reader)?;
+                Self::Float { min, max }
+            }
+            "brigadier:integer" => {
+                let (min, max) = read_bounds(reader)?;
+                Self::Integer { min, max }
+            }
+            "brigadier:long" => {
+                let (min, max) = read_bounds(reader)?;
+                Self::Long { min, max }
+            }
+            "brigadier:string" => Self::String(match VarInt::minecraft_read(reader)?.0 {
+                0 => StringKind::SingleWord,
+                1 => StringKind::QuotablePhrase,
+                2 => StringKind::GreedyPhrase,
+                kind => Err(CommandError::InvalidStringKind(kind))?,
+            }),
+            "minecraft:entity" => {
+                let flags = u8::minecraft_read(reader)?;
+                Self::Entity {
+                    single: flags & ENTITY_SINGLE != 0,
+                    players_only: flags & ENTITY_PLAYERS_ONLY != 0,
+                }
+            }
+            "minecraft:score_holder" => Self::ScoreHolder {
+                allow_multiple: u8::minecraft_read(reader)? & SCORE_HOLDER_MULTIPLE != 0,
+            },
+            "minecraft:range" => Self::Range {
+                decimals: bool::minecraft_read(reader)?,
+            },
+            "minecraft:resource" => Self::Resource {
+                registry: Identifier::minecraft_read(reader)?,
+            },
+            "minecraft:resource_or_tag" => Self::ResourceOrTag {
+                registry: Identifier::minecraft_read(reader)?,
+            },
+            name if PARSERS_WITHOUT_PROPERTIES.contains(&name) => Self::Other(identifier),
+            // The properties of an unknown parser cannot be skipped, so the
+            // rest of the packet cannot be read either.
+            _ => Err(CommandError::UnknownParser(identifier.0))?,
+        })
+    }
+
+    fn write(&self, writer: &mut impl Write) -> Result<()> {
+        Identifier(self.identifier().to_owned()).minecraft_write(writer)?;
+        match self {
+            Self::Bool | Self::Other(_) => Ok(()),
+            Self::Double { min, max } => write_bounds(min, max, writer),
+            Self::Float { min, max } => write_bounds(min, max, writer),
+            Self::Integer { min, max } => write_bounds(min, max, writer),
+            Self::Long { min, max } => write_bounds(min, max, writer),
+            Self::String(kind) => VarInt(*kind as i32).minecraft_write(writer),
+            Self::Entity {
+                single,
+                players_only,
+            } => {
+                let mut flags = 0;
+                if *single {
+                    flags |= ENTITY_SINGLE;
+                }
+                if *players_only {
+                    flags |= ENTITY_PLAYERS_ONLY;
+                }
+                flags.minecraft_write(writer)
+            }
+            Self::ScoreHolder { allow_multiple } => {
+                let flags = if *allow_multiple {
+                    SCORE_HOLDER_MULTIPLE
+                } else {
+                    0
+                };
+                flags.minecraft_write(writer)
+            }
+            Self::Range { decimals } => decimals.minecraft_write(writer),
+            Self::Resource { registry } | Self::ResourceOrTag { registry } => {
+                registry.minecraft_write(writer)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CommandNodeKind {
+    #[default]
+    Root,
+    Literal(String),
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+    },
+}
+
+/// One node of the Brigadier command graph sent in `DeclareCommands`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandNode {
+    pub kind: CommandNodeKind,
+    /// Whether a command may end at this node.
+    pub executable: bool,
+    pub children: Vec<VarInt>,
+    /// The node parsing continues from after this one, as `execute ... run`
+    /// continues from the root.
+    pub redirect: Option<VarInt>,
+    /// Where suggestions for an argument come from, such as
+    /// `minecraft:ask_server`.
+    pub suggestions: Option<Identifier>,
+}
+
+impl MinecraftIo for CommandNode {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        let mut flags = match self.kind {
+            CommandNodeKind::Root => 0,
+            CommandNodeKind::Literal(_) => 1,
+            CommandNodeKind::Argument { .. } => 2,
+        };
+        if self.executable {
+            flags |= FLAG_EXECUTABLE;
+        }
+        if self.redirect.is_some() {
+            flags |= FLAG_REDIRECT;
+        }
+        if self.suggestions.is_some() {
+            flags |= FLAG_SUGGESTIONS;
+        }
+        flags.minecraft_write(writer)?;
+        self.children.minecraft_write(writer)?;
+        self.redirect.minecraft_write(writer)?;
+        match &self.kind {
+            CommandNodeKind::Root => {}
+            CommandNodeKind::Literal(name) => name.minecraft_write(writer)?,
+            CommandNodeKind::Argument { name, parser } => {
+                name.minecraft_write(writer)?;
+                parser.write(writer)?;
+            }
+        }
+        self.suggestions.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let flags = u8::minecraft_read(reader)?;
+        let children = <Vec<VarInt>>::minecraft_read(reader)?;
+        let redirect = match flags & FLAG_REDIRECT {
+            0 => None,
+            _ => Some(VarInt::minecraft_read(reader)?),
+        };
+        let kind = match flags & NODE_TYPE_MASK {
+            0 => CommandNodeKind::Root,
+            1 => CommandNodeKind::Literal(String::minecraft_read(reader)?),
+            2 => {
+                let name = String::minecraft_read(reader)?;
+                let identifier = Identifier::minecraft_read(reader)?;
+                let parser = ArgumentParser::read(identifier, reader)?;
+                CommandNodeKind::Argument { name, parser }
+            }
+            node_type => Err(CommandError::InvalidNodeType(node_type))?,
+        };
+        let suggestions = match flags & FLAG_SUGGESTIONS {
+            0 => None,
+            _ => Some(Identifier::minecraft_read(reader)?),
+        };
+        Ok(Self {
+            kind,
+            executable: flags & FLAG_EXECUTABLE != 0,
+            children,
+            redirect,
+            suggestions,
+        })
+    }
+}
+
+/// A token that may come next in a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expected {
+    Literal(String),
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+    },
+}
+
+/// How far a command string matched the command graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandParse {
+    /// The indices of the nodes matched, in order.
+    pub path: Vec<usize>,
+    /// The byte offset of the first token that did not match, or the length
+    /// of the input if everything did.
+    pub consumed: usize,
+    /// Whether all of the input matched.
+    pub complete: bool,
+    /// Whether the matched command can be run as it is.
+    pub executable: bool,
+    /// What could come at `consumed`. When the input ends partway through
+    /// that token, literals are narrowed to those it is the start of.
+    pub expected: Vec<Expected>,
+}
+
+impl CommandParse {
+    /// Whether the command is worth sending: it all matched and ends at an
+    /// executable node.
+    pub fn is_valid(&self) -> bool {
+        self.complete && self.executable
+    }
+}
+
+/// The command graph of a server, for checking and completing commands
+/// without a round trip.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandTree {
+    pub nodes: Vec<CommandNode>,
+    pub root: usize,
+}
+
+/// A partial match: the nodes matched so far and where the input after them
+/// starts.
+struct Match {
+    path: Vec<usize>,
+    position: usize,
+}
+
+impl CommandTree {
+    pub fn new(nodes: Vec<CommandNode>, root: usize) -> Result<Self> {
+        let count = nodes.len();
+        let check = |node: i32| {
+            if node < 0 || node as usize >= count {
+                Err(CommandError::NodeOutOfRange { node, count })
+            } else {
+                Ok(())
+            }
+        };
+        check(root as i32)?;
+        for node in &nodes {
+            for child in node.children.iter().chain(&node.redirect) {
+                check(child.0)?;
+            }
+        }
+        Ok(Self { nodes, root })
+    }
+
+    pub fn node(&self, index: usize) -> &CommandNode {
+        &self.nodes[index]
+    }
+
+    /// The children of a node, following its redirect if it has one.
+    pub fn children(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let node = &self.nodes[index];
+        let source = match node.redirect {
+            Some(redirect) if node.children.is_empty() => &self.nodes[redirect.0 as usize],
+            _ => node,
+        };
+        source.children.iter().map(|child| child.0 as usize)
+    }
+
+    /// Finds the child of the root with this name, such as `tp`.
+    pub fn command(&self, name: &str) -> Option<usize> {
+        self.children(self.root)
+            .find(|&child| matches!(&self.nodes[child].kind, CommandNodeKind::Literal(literal) if literal == name))
+    }
+
+    /// Matches a command, with or without its leading `/`, against the
+    /// graph.
+    pub fn parse(&self, input: &str) -> CommandParse {
+        let offset = if input.starts_with('/') { 1 } else { 0 };
+        let text = &input[offset..];
+
+        let mut best = Match {
+            path: vec![],
+            position: 0,
+        };
+        let complete = self.walk(self.root, text, 0, &mut vec![], &mut best);
+        let last = best.path.last().copied().unwrap_or(self.root);
+
+        let remaining = &text[best.position..];
+        // Only the last token can still be being typed.
+        let partial = if remaining.contains(' ') { "" } else { remaining };
+        let expected = self
+            .children(last)
+            .filter_map(|child| match &self.nodes[child].kind {
+                CommandNodeKind::Root => None,
+                CommandNodeKind::Literal(name) => name
+                    .starts_with(partial)
+                    .then(|| Expected::Literal(name.clone())),
+                CommandNodeKind::Argument { name, parser } => Some(Expected::Argument {
+                    name: name.clone(),
+                    parser: parser.clone(),
+                }),
+            })
+            .collect();
+
+        CommandParse {
+            executable: complete && !best.path.is_empty() && self.nodes[last].executable,
+            path: best.path,
+            consumed: offset + best.position,
+            complete,
+            expected,
+        }
+    }
+
+    /// Tries each child of `node` against the text at `position`, depth
+    /// first. Returns whether the rest of the input matched; otherwise `best`
+    /// holds the match that got furthest.
+    fn walk(
+        &self,
+        node: usize,
+        text: &str,
+        position: usize,
+        path: &mut Vec<usize>,
+        best: &mut Match,
+    ) -> bool {
+        if position > best.position || best.path.is_empty() {
+            *best = Match {
+                path: path.clone(),
+                position,
+            };
+        }
+        if position == text.len() {
+            return true;
+        }
+
+        let rest = &text[position..];
+        // Brigadier prefers literals to arguments that would also match.
+        let mut children: Vec<usize> = self.children(node).collect();
+        children
+            .sort_by_key(|&child| !matches!(self.nodes[child].kind, CommandNodeKind::Literal(_)));
+        for child in children {
+            let Some(length) = self.match_node(child, rest) else {
+                continue;
+            };
+            let next = match rest[length..].strip_prefix(' ') {
+                Some(_) => position + length + 1,
+                None if length == rest.len() => position + length,
+                None => continue,
+            };
+            path.push(child);
+            if self.walk(child, text, next, path, best) {
+                *best = Match {
+                    path: path.clone(),
+                    position: text.len(),
+                };
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    /// The length of the token at the start of `text` if this node accepts
+    /// it.
+    fn match_node(&self, node: usize, text: &str) -> Option<usize> {
+        match &self.nodes[node].kind {
+            CommandNodeKind::Root => None,
+            CommandNodeKind::Literal(name) => {
+                let word = &text[..token_length(text)];
+                (word == name).then_some(word.len())
+            }
+            CommandNodeKind::Argument { parser, .. } => argument_length(parser, text),
+        }
+    }
+}
+
+/// The length of the space separated token at the start of `text`. Spaces
+/// inside brackets and quotes do not end it, so selectors like
+/// `@e[type=cow, limit=1]` and NBT are one token.
+fn token_length(text: &str) -> usize {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, character) in text.char_indices() {
+        match (quote, character) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(open), _) if character == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(character),
+            (None, '[' | '{' | '(') => depth += 1,
+            (None, ']' | '}' | ')') => depth -= 1,
+            (None, ' ') if depth <= 0 => return index,
+            (None, _) => {}
+        }
+    }
+    text.len()
+}
+
+/// The length of `count` tokens at the start of `text`, each accepted by
+/// `valid`.
+fn tokens_length(text: &str, count: usize, valid: impl Fn(&str) -> bool) -> Option<usize> {
+    let mut length = 0;
+    for index in 0..count {
+        if index > 0 {
+            text[length..].strip_prefix(' ')?;
+            length += 1;
+        }
+        let token_end = length + token_length(&text[length..]);
+        if !valid(&text[length..token_end]) {
+            return None;
+        }
+        length = token_end;
+    }
+    Some(length)
+}
+
+fn in_bounds<T: PartialOrd>(value: T, min: &Option<T>, max: &Option<T>) -> bool {
+    min.as_ref().is_none_or(|min| value >= *min) && max.as_ref().is_none_or(|max| value <= *max)
+}
+
+/// A coordinate: a number, or a `~` or `^` relative coordinate with an
+/// optional offset.
+fn is_coordinate(token: &str) -> bool {
+    let number = token
+        .strip_prefix('~')
+        .or_else(|| token.strip_prefix('^'))
+        .unwrap_or(token);
+    (number.is_empty() && !token.is_empty()) || number.parse::<f64>().is_ok()
+}
+
+/// The length of the argument at the start of `text` if the parser accepts
+/// it.
+fn argument_length(parser: &ArgumentParser, text: &str) -> Option<usize> {
+    let not_empty = |token: &str| !token.is_empty();
+    match parser {
+        ArgumentParser::Bool => tokens_length(text, 1, |token| token == "true" || token == "false"),
+        ArgumentParser::Double { min, max } => tokens_length(text, 1, |token| {
+            token
+                .parse()
+                .is_ok_and(|value: f64| in_bounds(value, min, max))
+        }),
+        ArgumentParser::Float { min, max } => tokens_length(text, 1, |token| {
+            token
+                .parse()
+                .is_ok_and(|value: f32| in_bounds(value, min, max))
+        }),
+        ArgumentParser::Integer { min, max } => tokens_length(text, 1, |token| {
+            token
+                .parse()
+                .is_ok_and(|value: i32| in_bounds(value, min, max))
+        }),
+        ArgumentParser::Long { min, max } => tokens_length(text, 1, |token| {
+            token
+                .parse()
+                .is_ok_and(|value: i64| in_bounds(value, min, max))
+        }),
+        ArgumentParser::String(StringKind::GreedyPhrase) => Some(text.len()),
+        ArgumentParser::String(_) => tokens_length(text, 1, not_empty),
+        ArgumentParser::Other(identifier) => match identifier.0.as_str() {
+            "minecraft:message" => (!text.is_empty()).then_some(text.len()),
+            "minecraft:block_pos" | "minecraft:vec3" => tokens_length(text, 3, is_coordinate),
+            "minecraft:column_pos" | "minecraft:vec2" | "minecraft:rotation" => {
+                tokens_length(text, 2, is_coordinate)
+            }
+            _ => tokens_length(text, 1, not_empty),
+        },
+        _ => tokens_length(text, 1, not_empty),
+    }
+}
+
+impl DeclareCommands {
+    pub fn tree(&self) -> Result<CommandTree> {
+        CommandTree::new(self.nodes.clone(), self.root_index.0 as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Direction, Packet, State};
+
+    fn literal(name: &str, executable: bool, children: &[i32]) -> CommandNode {
+        CommandNode {
+            kind: CommandNodeKind::Literal(name.to_owned()),
+            executable,
+            children: children.iter().copied().map(VarInt).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn argument(
+        name: &str,
+        parser: ArgumentParser,
+        executable: bool,
+        children: &[i32],
+    ) -> CommandNode {
+        CommandNode {
+            kind: CommandNodeKind::Argument {
+                name: name.to_owned(),
+                parser,
+            },
+            executable,
+            children: children.iter().copied().map(VarInt).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn other(identifier: &str) -> ArgumentParser {
+        ArgumentParser::Other(Identifier(identifier.to_owned()))
+    }
+
+    /// `tp <targets> <location>`, `time set (day|<time>)`,
+    /// `execute as <targets> run ...` and `say <message>`.
+    fn tree() -> CommandTree {
+        let entity = ArgumentParser::Entity {
+            single: false,
+            players_only: false,
+        };
+        let nodes = vec![
+            CommandNode {
+                children: vec![VarInt(1), VarInt(4), VarInt(8), VarInt(13)],
+                ..Default::default()
+            },
+            literal("tp", false, &[2]),
+            argument("targets", entity.clone(), false, &[3]),
+            argument("location", other("minecraft:vec3"), true, &[]),
+            literal("time", false, &[5]),
+            literal("set", false, &[6, 7]),
+            literal("day", true, &[]),
+            argument(
+                "time",
+                ArgumentParser::Integer {
+                    min: Some(0),
+                    max: None,
+                },
+                true,
+                &[],
+            ),
+            literal("execute", false, &[9]),
+            literal("as", false, &[10]),
+            argument("targets", entity, false, &[11]),
+            CommandNode {
+                redirect: Some(VarInt(0)),
+                ..literal("run", false, &[])
+            },
+            argument("message", other("minecraft:message"), true, &[]),
+            literal("say", false, &[12]),
+        ];
+        CommandTree::new(nodes, 0).unwrap()
+    }
+
+    fn literals(parse: &CommandParse) -> Vec<&str> {
+        parse
+            .expected
+            .iter()
+            .filter_map(|expected| match expected {
+                Expected::Literal(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_declare_commands_round_trip() {
+        let mut nodes = tree().nodes;
+        nodes[3].suggestions = Some(Identifier("minecraft:ask_server".to_owned()));
+        nodes.push(argument(
+            "amount",
+            ArgumentParser::Double {
+                min: None,
+                max: Some(1.0),
+            },
+            true,
+            &[],
+        ));
+        nodes.push(argument(
+            "word",
+            ArgumentParser::String(StringKind::QuotablePhrase),
+            false,
+            &[],
+        ));
+        nodes.push(argument(
+            "biome",
+            ArgumentParser::ResourceOrTag {
+                registry: Identifier("minecraft:worldgen/biome".to_owned()),
+            },
+            false,
+            &[],
+        ));
+        let packet = Packet::DeclareCommands(DeclareCommands {
+            nodes: nodes.clone(),
+            root_index: VarInt(0),
+        });
+        let mut payload = vec![];
+        packet.write_payload(&mut payload).unwrap();
+
+        let Packet::DeclareCommands(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected DeclareCommands");
+        };
+        assert_eq!(read.nodes, nodes);
+        let tree = read.tree().unwrap();
+        assert_eq!(tree.command("time"), Some(4));
+    }
+
+    #[test]
+    fn test_read_node() {
+        // An executable argument node with one child, a bounded integer and
+        // a suggestions type.
+        let mut bytes = vec![0x16, 1, 5, 5];
+        bytes.extend(b"count");
+        bytes.push(17);
+        bytes.extend(b"brigadier:integer");
+        bytes.extend([0x03, 0, 0, 0, 1, 0, 0, 0, 64, 20]);
+        bytes.extend(b"minecraft:ask_server");
+        let node = CommandNode::minecraft_read(&mut &bytes[..]).unwrap();
+        assert!(node.executable);
+        assert_eq!(node.children, [VarInt(5)]);
+        assert_eq!(
+            node.kind,
+            CommandNodeKind::Argument {
+                name: "count".to_owned(),
+                parser: ArgumentParser::Integer {
+                    min: Some(1),
+                    max: Some(64),
+                },
+            }
+        );
+        assert_eq!(node.suggestions.unwrap(), "minecraft:ask_server");
+
+        let mut unknown = vec![0x02, 0, 1, b'x', 11];
+        unknown.extend(b"mod:special");
+        assert!(CommandNode::minecraft_read(&mut &unknown[..]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_tree() {
+        let nodes = vec![literal("tp", false, &[3])];
+        assert!(CommandTree::new(nodes, 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_valid_commands() {
+        let tree = tree();
+        assert!(tree
+            .parse("/tp @e[type=cow, limit=1] ~ ~1 ^-2.5")
+            .is_valid());
+        assert!(tree.parse("time set day").is_valid());
+        assert!(tree.parse("/time set 6000").is_valid());
+        assert!(tree.parse("/say hello there  world").is_valid());
+        assert!(tree
+            .parse("/execute as @a run execute as Steve run time set day")
+            .is_valid());
+    }
+
+    #[test]
+    fn test_parse_incomplete_commands() {
+        let tree = tree();
+
+        let parse = tree.parse("/");
+        assert!(parse.complete && !parse.executable);
+        assert_eq!(literals(&parse), ["tp", "time", "execute", "say"]);
+
+        let parse = tree.parse("/ti");
+        assert!(!parse.complete);
+        assert_eq!(parse.consumed, 1);
+        assert_eq!(literals(&parse), ["time"]);
+
+        let parse = tree.parse("/time set ");
+        assert!(parse.complete && !parse.is_valid());
+        assert_eq!(parse.path, [4, 5]);
+        assert_eq!(literals(&parse), ["day"]);
+        assert!(parse.expected.contains(&Expected::Argument {
+            name: "time".to_owned(),
+            parser: ArgumentParser::Integer {
+                min: Some(0),
+                max: None,
+            },
+        }));
+
+        let parse = tree.parse("/execute as @a run ");
+        assert_eq!(literals(&parse), ["tp", "time", "execute", "say"]);
+    }
+
+    #[test]
+    fn test_parse_typos() {
+        let tree = tree();
+
+        let parse = tree.parse("/time st day");
+        assert!(!parse.is_valid());
+        assert_eq!(parse.consumed, "/time ".len());
+        assert_eq!(literals(&parse), ["set"]);
+
+        // Out of the argument's bounds.
+        let parse = tree.parse("/time set -5");
+        assert!(!parse.is_valid());
+        assert_eq!(parse.consumed, "/time set ".len());
+
+        // Too few coordinates.
+        let parse = tree.parse("/tp @p 1 2");
+        assert!(!parse.is_valid());
+        assert_eq!(parse.consumed, "/tp @p ".len());
+
+        // Trailing input after a complete command.
+        let parse = tree.parse("/time set day now");
+        assert!(!parse.is_valid());
+        assert_eq!(parse.path, [4, 5, 6]);
+        assert!(parse.expected.is_empty());
+    }
+}
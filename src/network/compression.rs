@@ -0,0 +1,101 @@
+use crate::network::limits::Limits;
+use crate::network::packets::FrameError;
+use crate::network::VarInt;
+use anyhow::Result;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// Wraps a packet payload (ID and body) in the compressed frame body format:
+/// the uncompressed data length followed by the zlib stream, or a data length
+/// of 0 followed by the raw payload if it is smaller than `threshold`.
+pub fn compress_payload(payload: &[u8], threshold: i32) -> Result<Vec<u8>> {
+    let mut frame = vec![];
+    if payload.len() < threshold.max(0) as usize {
+        VarInt(0).write(&mut frame)?;
+        frame.extend_from_slice(payload);
+        return Ok(frame);
+    }
+
+    VarInt(payload.len() as i32).write(&mut frame)?;
+    let mut encoder = ZlibEncoder::new(frame, Compression::default());
+    encoder.write_all(payload)?;
+    Ok(encoder.finish()?)
+}
+
+/// Recovers the packet payload from a compressed frame body, replacing the
+/// contents of `payload` so its allocation can be reused from one packet to
+/// the next.
+pub fn decompress_payload_into(frame: &[u8], payload: &mut Vec<u8>) -> Result<()> {
+    payload.clear();
+    let mut reader = frame;
+    let data_length = VarInt::decode(&mut reader)?;
+    if data_length.0 == 0 {
+        payload.extend_from_slice(reader);
+        return Ok(());
+    }
+    if data_length.0 < 0 || data_length.0 as usize > Limits::current().max_payload_length {
+        Err(FrameError::InvalidLength(data_length.0))?;
+    }
+
+    payload.reserve(data_length.0 as usize);
+    ZlibDecoder::new(reader)
+        .take(data_length.0 as u64 + 1)
+        .read_to_end(payload)?;
+    if payload.len() != data_length.0 as usize {
+        Err(FrameError::DataLengthMismatch {
+            expected: data_length.0,
+            actual: payload.len(),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompress_payload(frame: &[u8]) -> Result<Vec<u8>> {
+        let mut payload = vec![];
+        decompress_payload_into(frame, &mut payload)?;
+        Ok(payload)
+    }
+
+    #[test]
+    fn test_below_threshold_is_uncompressed() {
+        let frame = compress_payload(&[1, 2, 3], 256).unwrap();
+        assert_eq!(frame, &[0, 1, 2, 3]);
+        assert_eq!(decompress_payload(&frame).unwrap(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let payload = vec![7u8; 1000];
+        let frame = compress_payload(&payload, 256).unwrap();
+        assert_eq!(&frame[..2], &[0xe8, 0x07]);
+        assert!(frame.len() < payload.len());
+        assert_eq!(decompress_payload(&frame).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_data_length_mismatch() {
+        let mut frame = compress_payload(&[7u8; 300], 256).unwrap();
+        frame[0] = 0xad;
+        assert!(decompress_payload(&frame).is_err());
+    }
+
+    #[test]
+    fn test_data_length_limit() {
+        let frame = compress_payload(&[7u8; 300], 256).unwrap();
+        let limits = Limits {
+            max_payload_length: 299,
+            ..Limits::DEFAULT
+        };
+        let error = limits.scope(|| decompress_payload(&frame)).unwrap_err();
+        assert!(matches!(
+            error.downcast::<FrameError>().unwrap(),
+            FrameError::InvalidLength(300)
+        ));
+    }
+}
@@ -1,61 +1,270 @@
-use crate::network::{packets, Packet, VarInt};
-use std::io::{BufReader, BufWriter, Read, Write};
+use crate::network::encryption::{
+    encrypt_with_public_key, generate_shared_secret, server_hash, SharedSecret,
+};
+use crate::network::limits::Limits;
+use crate::network::machine::ProtocolMachine;
+use crate::network::packets::PacketRef;
+use crate::network::session::{Account, HttpSessionService, SessionService};
+use crate::network::{packets, ByteArray, Chat, LengthPrefixedByteArray, Packet, VarInt};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{ErrorKind, Read, Write};
 use std::net::TcpStream;
-use winit::event::VirtualKeyCode::S;
 
-enum Direction {
+#[cfg(test)]
+mod tests;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
     ServerBound,
     ClientBound,
 }
 
-enum State {
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum State {
     Handshake,
     Status,
     Login,
     Play,
 }
 
-struct Connection {
-    direction: Direction,
-    state: State,
+pub const PROTOCOL_VERSION: i32 = 758;
+
+/// How much is read from the stream at a time.
+const READ_SIZE: usize = 8192;
 
-    reader: Box<dyn Read>,
-    writer: Box<dyn Write>,
+#[derive(Clone, Debug)]
+pub enum LoginError {
+    Disconnected(Chat),
+    NotAuthenticated,
+    UnexpectedPacket(i32),
 }
 
-pub fn connect_to_server(address: &str, port: u16) -> anyhow::Result<()> {
-    let stream = TcpStream::connect((address, port))?;
-    let reader = Box::new(BufReader::new(stream.try_clone().unwrap()));
-    let writer = Box::new(stream);
+impl Display for LoginError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
 
-    let conn = Connection {
-        direction: Direction::ClientBound,
-        state: State::Handshake,
+impl Error for LoginError {}
 
-        reader,
-        writer,
-    };
+#[derive(Copy, Clone, Debug)]
+pub enum StateError {
+    InvalidTransition(State, State),
+}
 
-    Ok(())
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
-pub fn test_handshake(address: &str, port: u16) {
-    let packet = Packet::Handshake(packets::Handshake {
-        protocol_version: VarInt(758),
-        server_address: address.to_owned(),
-        server_port: port,
-        next_state: VarInt(1),
-    });
+impl Error for StateError {}
 
-    let mut buffer: Vec<u8> = vec![];
-    // packet.write_to(&mut buffer);
-    println!("{:x?}", buffer);
+pub struct Connection {
+    machine: ProtocolMachine,
+
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
 }
 
-pub fn write_packet() -> anyhow::Result<()> {
-    Ok(())
+impl Connection {
+    pub fn new(
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+        direction: Direction,
+    ) -> Self {
+        Self {
+            machine: ProtocolMachine::new(direction),
+
+            reader,
+            writer,
+        }
+    }
+
+    pub fn from_stream(stream: TcpStream, direction: Direction) -> anyhow::Result<Self> {
+        let reader = Box::new(stream.try_clone()?);
+        let writer = Box::new(stream);
+        Ok(Self::new(reader, writer, direction))
+    }
+
+    /// Connects to a server and logs in as `account`, returning a connection
+    /// in the Play state.
+    pub fn connect(
+        address: &str,
+        port: u16,
+        account: &Account,
+        session: &dyn SessionService,
+    ) -> anyhow::Result<Self> {
+        let stream = TcpStream::connect((address, port))?;
+        let mut connection = Self::from_stream(stream, Direction::ClientBound)?;
+        connection.handshake(address, port, State::Login)?;
+        connection.login(account, session)?;
+        Ok(connection)
+    }
+
+    /// Splits the connection into the protocol state and the stream halves
+    /// it was driving, for code that runs its own I/O loop.
+    pub fn into_parts(self) -> (ProtocolMachine, Box<dyn Read + Send>, Box<dyn Write + Send>) {
+        (self.machine, self.reader, self.writer)
+    }
+
+    pub fn state(&self) -> State {
+        self.machine.state()
+    }
+
+    pub fn set_state(&mut self, state: State) {
+        self.machine.set_state(state);
+    }
+
+    pub fn compression_threshold(&self) -> Option<i32> {
+        self.machine.compression_threshold()
+    }
+
+    /// Switches framing to the compressed format. A negative threshold turns
+    /// compression back off, matching the meaning of `SetCompression`.
+    pub fn set_compression(&mut self, threshold: Option<i32>) {
+        self.machine.set_compression(threshold);
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.machine.limits()
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.machine.set_limits(limits);
+    }
+
+    pub fn read_packet(&mut self) -> anyhow::Result<Packet> {
+        Ok(self.read_packet_ref()?.into_owned())
+    }
+
+    /// Reads a packet like `read_packet`, but borrows the large fields of
+    /// the packets [`PacketRef`] supports from the connection's buffer.
+    pub fn read_packet_ref(&mut self) -> anyhow::Result<PacketRef<'_>> {
+        let mut buffer = [0; READ_SIZE];
+        while !self.machine.has_packet()? {
+            let count = self.reader.read(&mut buffer)?;
+            if count == 0 {
+                Err(std::io::Error::from(ErrorKind::UnexpectedEof))?;
+            }
+            self.machine.receive(&buffer[..count]);
+        }
+        Ok(self
+            .machine
+            .next_packet_ref()?
+            .expect("a whole frame was received"))
+    }
+
+    pub fn write_packet(&mut self, packet: &Packet) -> anyhow::Result<()> {
+        self.machine.send(packet)?;
+        self.writer.write_all(self.machine.outgoing())?;
+        self.writer.flush()?;
+        self.machine.take_outgoing();
+        Ok(())
+    }
+
+    /// Encrypts both directions of the stream with AES/CFB8 keyed with
+    /// `shared_secret`. Everything after the `EncryptionResponse` is
+    /// encrypted.
+    pub fn enable_encryption(&mut self, shared_secret: &SharedSecret) {
+        self.machine.enable_encryption(shared_secret);
+    }
+
+    /// Sends the `Handshake` that moves the connection to `next_state`, which
+    /// must be either Status or Login.
+    pub fn handshake(&mut self, address: &str, port: u16, next_state: State) -> anyhow::Result<()> {
+        let next_state = match (self.state(), next_state) {
+            (State::Handshake, State::Status) => VarInt(1),
+            (State::Handshake, State::Login) => VarInt(2),
+            (current, next) => Err(StateError::InvalidTransition(current, next))?,
+        };
+        self.write_packet(&Packet::Handshake(packets::Handshake {
+            protocol_version: VarInt(PROTOCOL_VERSION),
+            server_address: address.to_owned(),
+            server_port: port,
+            next_state,
+        }))
+    }
+
+    /// Sends `LoginStart` and answers the server until it accepts the login,
+    /// switching the connection to the Play state. The handshake must already
+    /// have moved the connection to the Login state.
+    pub fn login(
+        &mut self,
+        account: &Account,
+        session: &dyn SessionService,
+    ) -> anyhow::Result<packets::LoginSuccess> {
+        self.write_packet(&Packet::LoginStart(packets::LoginStart {
+            username: account.username.clone(),
+        }))?;
+
+        loop {
+            match self.read_packet()? {
+                Packet::EncryptionRequest(request) => {
+                    self.answer_encryption_request(&request, account, session)?
+                }
+                Packet::SetCompression(_) => {}
+                Packet::LoginPluginRequest(request) => {
+                    // No login plugin channels are understood, which the server
+                    // has to accept.
+                    self.write_packet(&Packet::LoginPluginResponse(packets::LoginPluginResponse {
+                        message_id: request.message_id,
+                        success: false,
+                        data: ByteArray::new(),
+                    }))?
+                }
+                Packet::LoginSuccess(success) => return Ok(success),
+                Packet::Disconnect(packets::Disconnect { reason }) => {
+                    Err(LoginError::Disconnected(reason))?
+                }
+                packet => Err(LoginError::UnexpectedPacket(packet.id().2 .0))?,
+            }
+        }
+    }
+
+    /// Performs the client side of online-mode authentication: joins the
+    /// session server with the hash of a fresh shared secret, sends the secret
+    /// and verify token back encrypted with the server's key and turns on
+    /// stream encryption.
+    fn answer_encryption_request(
+        &mut self,
+        request: &packets::EncryptionRequest,
+        account: &Account,
+        session: &dyn SessionService,
+    ) -> anyhow::Result<()> {
+        let access_token = account
+            .access_token
+            .as_deref()
+            .ok_or(LoginError::NotAuthenticated)?;
+        let public_key = &request.public_key.value;
+        let shared_secret = generate_shared_secret();
+
+        let hash = server_hash(&request.server_id, &shared_secret, public_key);
+        session.join(access_token, account.uuid, &hash)?;
+
+        let response = packets::EncryptionResponse {
+            shared_secret: LengthPrefixedByteArray::from_vec(encrypt_with_public_key(
+                public_key,
+                &shared_secret,
+            )?),
+            verify_token: LengthPrefixedByteArray::from_vec(encrypt_with_public_key(
+                public_key,
+                &request.verify_token.value,
+            )?),
+        };
+        self.write_packet(&Packet::EncryptionResponse(response))?;
+        self.enable_encryption(&shared_secret);
+        Ok(())
+    }
 }
 
-pub fn read_packet() -> anyhow::Result<()> {
-    Ok(())
+/// Connects to a server and logs in as `account`, authenticating with
+/// Mojang's session server if the server is in online mode.
+pub fn connect_to_server(
+    address: &str,
+    port: u16,
+    account: &Account,
+) -> anyhow::Result<Connection> {
+    Connection::connect(address, port, account, &HttpSessionService::default())
 }
@@ -0,0 +1,380 @@
+    use super::*;
+    use crate::network::packets::*;
+    use crate::network::{ByteArray, Identifier};
+    use rsa::pkcs8::EncodePublicKey;
+    use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+    use std::thread;
+    use uuid::Uuid;
+
+    fn loopback_pair() -> (Connection, Connection) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (
+            Connection::from_stream(client, Direction::ClientBound).unwrap(),
+            Connection::from_stream(server, Direction::ServerBound).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_compression_switches_after_set_compression() {
+        let (mut client, mut server) = loopback_pair();
+        client.set_state(State::Login);
+        server.set_state(State::Login);
+
+        server
+            .write_packet(&Packet::SetCompression(SetCompression {
+                threshold: VarInt(64),
+            }))
+            .unwrap();
+        assert_eq!(server.compression_threshold(), Some(64));
+
+        let packet = client.read_packet().unwrap();
+        assert!(matches!(packet, Packet::SetCompression(_)));
+        assert_eq!(client.compression_threshold(), Some(64));
+
+        let data = ByteArray(vec![0x55; 500]);
+        server
+            .write_packet(&Packet::LoginPluginRequest(LoginPluginRequest {
+                message_id: VarInt(1),
+                channel: Identifier("test:big".to_owned()),
+                data: data.clone(),
+            }))
+            .unwrap();
+        server
+            .write_packet(&Packet::LoginPluginRequest(LoginPluginRequest {
+                message_id: VarInt(2),
+                channel: Identifier("test:small".to_owned()),
+                data: ByteArray(vec![1, 2, 3]),
+            }))
+            .unwrap();
+
+        match client.read_packet().unwrap() {
+            Packet::LoginPluginRequest(request) => {
+                assert_eq!(request.message_id, VarInt(1));
+                assert_eq!(request.data, data);
+            }
+            packet => panic!("unexpected {packet:?}"),
+        }
+        match client.read_packet().unwrap() {
+            Packet::LoginPluginRequest(request) => {
+                assert_eq!(request.channel, "test:small");
+                assert_eq!(*request.data, [1, 2, 3]);
+            }
+            packet => panic!("unexpected {packet:?}"),
+        }
+
+        let secret = LengthPrefixedByteArray::from_vec(vec![0xaa; 100]);
+        client
+            .write_packet(&Packet::EncryptionResponse(EncryptionResponse {
+                shared_secret: secret.clone(),
+                verify_token: LengthPrefixedByteArray::from_vec(vec![1]),
+            }))
+            .unwrap();
+        match server.read_packet().unwrap() {
+            Packet::EncryptionResponse(response) => {
+                assert_eq!(response.shared_secret.value, secret.value)
+            }
+            packet => panic!("unexpected {packet:?}"),
+        }
+    }
+
+    #[test]
+    fn test_read_packet_ref() {
+        let (mut client, mut server) = loopback_pair();
+        client.set_state(State::Play);
+        server.set_state(State::Play);
+        client.set_compression(Some(64));
+        server.set_compression(Some(64));
+
+        let data = vec![0x33; 1000];
+        server
+            .write_packet(&Packet::ServerPluginMessage(ServerPluginMessage {
+                channel: Identifier("test:big".to_owned()),
+                data: ByteArray(data.clone()),
+            }))
+            .unwrap();
+        server
+            .write_packet(&Packet::KeepAliveRequest(KeepAliveRequest {
+                keep_alive_id: 3,
+            }))
+            .unwrap();
+
+        match client.read_packet_ref().unwrap() {
+            PacketRef::ServerPluginMessage(message) => {
+                assert_eq!(message.channel, "test:big");
+                assert_eq!(message.data, data);
+            }
+            packet => panic!("unexpected {packet:?}"),
+        }
+        assert!(matches!(
+            client.read_packet_ref().unwrap(),
+            PacketRef::Packet(Packet::KeepAliveRequest(KeepAliveRequest {
+                keep_alive_id: 3
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_negative_threshold_disables_compression() {
+        let (mut client, _server) = loopback_pair();
+        client.set_compression(Some(256));
+        assert_eq!(client.compression_threshold(), Some(256));
+        client.set_compression(Some(-1));
+        assert_eq!(client.compression_threshold(), None);
+    }
+
+    #[derive(Default)]
+    struct RecordingSession {
+        joins: Mutex<Vec<(String, Uuid, String)>>,
+    }
+
+    impl SessionService for RecordingSession {
+        fn join(&self, access_token: &str, profile: Uuid, server_hash: &str) -> anyhow::Result<()> {
+            self.joins
+                .lock()
+                .unwrap()
+                .push((access_token.to_owned(), profile, server_hash.to_owned()));
+            Ok(())
+        }
+    }
+
+    fn online_account() -> Account {
+        Account {
+            username: "tester".to_owned(),
+            uuid: Uuid::from_u128(42),
+            access_token: Some("token".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_online_login() {
+        let (mut client, mut server) = loopback_pair();
+        client.set_state(State::Login);
+        server.set_state(State::Login);
+
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let public_key = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .unwrap()
+            .as_bytes()
+            .to_vec();
+
+        let server_public_key = public_key.clone();
+        let server_thread = thread::spawn(move || {
+            let Packet::LoginStart(start) = server.read_packet().unwrap() else {
+                panic!("expected LoginStart");
+            };
+            server
+                .write_packet(&Packet::EncryptionRequest(EncryptionRequest {
+                    server_id: String::new(),
+                    public_key: LengthPrefixedByteArray::from_vec(server_public_key),
+                    verify_token: LengthPrefixedByteArray::from_vec(vec![1, 2, 3, 4]),
+                }))
+                .unwrap();
+
+            let Packet::EncryptionResponse(response) = server.read_packet().unwrap() else {
+                panic!("expected EncryptionResponse");
+            };
+            let verify_token = private_key
+                .decrypt(Pkcs1v15Encrypt, &response.verify_token.value)
+                .unwrap();
+            assert_eq!(verify_token, [1, 2, 3, 4]);
+            let shared_secret: SharedSecret = private_key
+                .decrypt(Pkcs1v15Encrypt, &response.shared_secret.value)
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            server.enable_encryption(&shared_secret);
+            server
+                .write_packet(&Packet::SetCompression(SetCompression {
+                    threshold: VarInt(16),
+                }))
+                .unwrap();
+            server
+                .write_packet(&Packet::LoginSuccess(LoginSuccess {
+                    uuid: Uuid::from_u128(42),
+                    usename: start.username,
+                }))
+                .unwrap();
+            shared_secret
+        });
+
+        let session = RecordingSession::default();
+        let success = client.login(&online_account(), &session).unwrap();
+        assert_eq!(success.usename, "tester");
+        assert_eq!(client.state(), State::Play);
+        assert_eq!(client.compression_threshold(), Some(16));
+
+        let shared_secret = server_thread.join().unwrap();
+        let joins = session.joins.lock().unwrap();
+        assert_eq!(
+            *joins,
+            [(
+                "token".to_owned(),
+                Uuid::from_u128(42),
+                server_hash("", &shared_secret, &public_key)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_encryption_requires_access_token() {
+        let (mut client, mut server) = loopback_pair();
+        client.set_state(State::Login);
+        server.set_state(State::Login);
+        server
+            .write_packet(&Packet::EncryptionRequest(EncryptionRequest::default()))
+            .unwrap();
+
+        let session = RecordingSession::default();
+        let error = client
+            .login(&Account::offline("tester"), &session)
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<LoginError>(),
+            Some(LoginError::NotAuthenticated)
+        ));
+        assert!(session.joins.lock().unwrap().is_empty());
+    }
+
+    /// Accepts a single client, checks it through the handshake and login
+    /// and hands the Play state connection to `play`.
+    fn fake_server<T: Send + 'static>(
+        play: impl FnOnce(&mut Connection) -> T + Send + 'static,
+    ) -> (u16, thread::JoinHandle<T>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut server = Connection::from_stream(stream, Direction::ServerBound).unwrap();
+
+            let Packet::Handshake(handshake) = server.read_packet().unwrap() else {
+                panic!("expected Handshake");
+            };
+            assert_eq!(handshake.protocol_version, VarInt(PROTOCOL_VERSION));
+            assert_eq!(handshake.server_address, "127.0.0.1");
+            assert_eq!(handshake.server_port, port);
+            assert_eq!(server.state(), State::Login);
+
+            let Packet::LoginStart(start) = server.read_packet().unwrap() else {
+                panic!("expected LoginStart");
+            };
+            server
+                .write_packet(&Packet::LoginPluginRequest(LoginPluginRequest {
+                    message_id: VarInt(9),
+                    channel: Identifier("velocity:player_info".to_owned()),
+                    data: ByteArray(vec![1]),
+                }))
+                .unwrap();
+            let Packet::LoginPluginResponse(response) = server.read_packet().unwrap() else {
+                panic!("expected LoginPluginResponse");
+            };
+            assert_eq!(response.message_id, VarInt(9));
+            assert!(!response.success);
+
+            server
+                .write_packet(&Packet::SetCompression(SetCompression {
+                    threshold: VarInt(256),
+                }))
+                .unwrap();
+            server
+                .write_packet(&Packet::LoginSuccess(LoginSuccess {
+                    uuid: Uuid::from_u128(7),
+                    usename: start.username,
+                }))
+                .unwrap();
+            assert_eq!(server.state(), State::Play);
+
+            play(&mut server)
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_connect_to_play_state() {
+        let (port, server) = fake_server(|server| {
+            server
+                .write_packet(&Packet::ServerPluginMessage(ServerPluginMessage {
+                    channel: Identifier("minecraft:brand".to_owned()),
+                    data: ByteArray(b"\x07vanilla".to_vec()),
+                }))
+                .unwrap();
+            server
+                .write_packet(&Packet::KeepAliveRequest(KeepAliveRequest {
+                    keep_alive_id: 1234,
+                }))
+                .unwrap();
+            server.read_packet().unwrap()
+        });
+
+        let session = RecordingSession::default();
+        let mut client =
+            Connection::connect("127.0.0.1", port, &Account::offline("tester"), &session).unwrap();
+        assert_eq!(client.state(), State::Play);
+        assert_eq!(client.compression_threshold(), Some(256));
+
+        let Packet::ServerPluginMessage(brand) = client.read_packet().unwrap() else {
+            panic!("expected ServerPluginMessage");
+        };
+        assert_eq!(brand.channel, "minecraft:brand");
+        let Packet::KeepAliveRequest(keep_alive) = client.read_packet().unwrap() else {
+            panic!("expected KeepAliveRequest");
+        };
+        client
+            .write_packet(&Packet::KeepAliveResponse(KeepAliveResponse {
+                keep_alive_id: keep_alive.keep_alive_id,
+            }))
+            .unwrap();
+
+        let Packet::KeepAliveResponse(response) = server.join().unwrap() else {
+            panic!("expected KeepAliveResponse");
+        };
+        assert_eq!(response.keep_alive_id, 1234);
+        assert!(session.joins.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_login_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut server = Connection::from_stream(stream, Direction::ServerBound).unwrap();
+            server.read_packet().unwrap();
+            server.read_packet().unwrap();
+            server
+                .write_packet(&Packet::Disconnect(Disconnect {
+                    reason: Chat(r#"{"text":"Server is full"}"#.to_owned()),
+                }))
+                .unwrap();
+        });
+
+        let session = RecordingSession::default();
+        let error = Connection::connect("127.0.0.1", port, &Account::offline("tester"), &session)
+            .err()
+            .unwrap();
+        match error.downcast_ref::<LoginError>() {
+            Some(LoginError::Disconnected(reason)) => {
+                assert_eq!(reason.component().unwrap().to_plain(), "Server is full")
+            }
+            _ => panic!("unexpected error {error}"),
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_handshake_only_from_handshake_state() {
+        let (mut client, _server) = loopback_pair();
+        client.set_state(State::Login);
+        assert!(client.handshake("localhost", 25565, State::Login).is_err());
+
+        client.set_state(State::Handshake);
+        assert!(client.handshake("localhost", 25565, State::Play).is_err());
+        client.handshake("localhost", 25565, State::Status).unwrap();
+        assert_eq!(client.state(), State::Status);
+    }
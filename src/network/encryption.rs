@@ -0,0 +1,137 @@
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use anyhow::Result;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPublicKey};
+use sha1::{Digest, Sha1};
+
+type Aes128Cfb8Encryptor = cfb8::Encryptor<aes::Aes128>;
+type Aes128Cfb8Decryptor = cfb8::Decryptor<aes::Aes128>;
+
+pub type SharedSecret = [u8; 16];
+
+pub fn generate_shared_secret() -> SharedSecret {
+    rand::random()
+}
+
+/// Encrypts `data` with the server's public key, given in the DER
+/// SubjectPublicKeyInfo form sent in `EncryptionRequest`.
+pub fn encrypt_with_public_key(public_key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let key = RsaPublicKey::from_public_key_der(public_key)?;
+    Ok(key.encrypt(&mut rand::thread_rng(), Pkcs1v15Encrypt, data)?)
+}
+
+/// The server ID sent to the session server: the SHA-1 of the inputs printed
+/// as a signed, two's complement hex number without leading zeros.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    let mut digest: [u8; 20] = hasher.finalize().into();
+
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            *byte = !*byte;
+            if carry {
+                (*byte, carry) = byte.overflowing_add(1);
+            }
+        }
+    }
+
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    let hex = hex.trim_start_matches('0');
+    if negative {
+        format!("-{hex}")
+    } else {
+        hex.to_owned()
+    }
+}
+
+/// The AES-128/CFB8 stream cipher used once encryption is enabled. The
+/// shared secret is both the key and the IV, and the cipher state carries
+/// over from one packet to the next, so each direction keeps its own half.
+pub fn cipher_pair(shared_secret: &SharedSecret) -> (Encryptor, Decryptor) {
+    let key = shared_secret.into();
+    (
+        Encryptor(Aes128Cfb8Encryptor::new(key, key)),
+        Decryptor(Aes128Cfb8Decryptor::new(key, key)),
+    )
+}
+
+pub struct Encryptor(Aes128Cfb8Encryptor);
+
+impl Encryptor {
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.chunks_mut(1) {
+            self.0.encrypt_block_mut(byte.into());
+        }
+    }
+}
+
+pub struct Decryptor(Aes128Cfb8Decryptor);
+
+impl Decryptor {
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data.chunks_mut(1) {
+            self.0.decrypt_block_mut(byte.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::pkcs8::EncodePublicKey;
+    use rsa::RsaPrivateKey;
+
+    #[test]
+    fn test_server_hash() {
+        assert_eq!(
+            server_hash("Notch", &[], &[]),
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+        );
+        assert_eq!(
+            server_hash("jeb_", &[], &[]),
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+        );
+        assert_eq!(
+            server_hash("simon", &[], &[]),
+            "88e16a1019277b15d58faf0541e11910eb756f6"
+        );
+    }
+
+    #[test]
+    fn test_cipher_is_a_stream() {
+        let secret = [3u8; 16];
+        let plaintext = b"the quick brown fox jumps over the lazy dog".to_vec();
+
+        let (mut whole, _) = cipher_pair(&secret);
+        let mut expected = plaintext.clone();
+        whole.encrypt(&mut expected);
+        assert_ne!(expected, plaintext);
+
+        let (mut pieces, mut decryptor) = cipher_pair(&secret);
+        let mut actual = plaintext.clone();
+        let (first, second) = actual.split_at_mut(7);
+        pieces.encrypt(first);
+        pieces.encrypt(second);
+        assert_eq!(actual, expected);
+
+        decryptor.decrypt(&mut actual[..20]);
+        decryptor.decrypt(&mut actual[20..]);
+        assert_eq!(actual, plaintext);
+    }
+
+    #[test]
+    fn test_encrypt_with_public_key() {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let public_key = private_key.to_public_key().to_public_key_der().unwrap();
+
+        let encrypted = encrypt_with_public_key(public_key.as_bytes(), b"secret").unwrap();
+        assert_eq!(encrypted.len(), 128);
+        let decrypted = private_key.decrypt(Pkcs1v15Encrypt, &encrypted).unwrap();
+        assert_eq!(decrypted, b"secret");
+    }
+}
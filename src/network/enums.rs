@@ -0,0 +1,450 @@
+use crate::network::chat::Color;
+use crate::network::{MinecraftIo, VarInt};
+use anyhow::Result;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EnumError {
+    /// An ID, usually of an `Unknown` value, that does not fit the type the
+    /// enum is sent as.
+    OutOfRange(i32),
+}
+
+impl Display for EnumError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for EnumError {}
+
+/// An enum with a numeric ID on the wire.
+pub trait ProtocolEnum: Copy {
+    fn from_id(id: i32) -> Self;
+    fn id(self) -> i32;
+}
+
+/// The integer types enums are sent as.
+pub trait EnumRepr: MinecraftIo {
+    fn into_id(self) -> i32;
+    fn from_id(id: i32) -> Result<Self>;
+}
+
+impl EnumRepr for VarInt {
+    fn into_id(self) -> i32 {
+        self.0
+    }
+
+    fn from_id(id: i32) -> Result<Self> {
+        Ok(VarInt(id))
+    }
+}
+
+impl EnumRepr for u8 {
+    fn into_id(self) -> i32 {
+        self as i32
+    }
+
+    fn from_id(id: i32) -> Result<Self> {
+        Ok(u8::try_from(id).map_err(|_| EnumError::OutOfRange(id))?)
+    }
+}
+
+impl EnumRepr for i8 {
+    fn into_id(self) -> i32 {
+        self as i32
+    }
+
+    fn from_id(id: i32) -> Result<Self> {
+        Ok(i8::try_from(id).map_err(|_| EnumError::OutOfRange(id))?)
+    }
+}
+
+/// Declares enums with their IDs and the type they are sent as. Each gets an
+/// `Unknown` variant, so values this client does not know are kept and can be
+/// sent back unchanged. Writing an `Unknown` value too large for the type the
+/// enum is sent as fails rather than sending a different value.
+macro_rules! protocol_enums {
+    (
+        $($(#[$meta:meta])* $name:ident: $repr:ty {
+            $($variant:ident = $id:literal),* $(,)?
+        })*
+    ) => {
+        $(
+            $(#[$meta])*
+            #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+            pub enum $name {
+                $($variant,)*
+                Unknown(i32),
+            }
+
+            impl Default for $name {
+                fn default() -> Self {
+                    <Self as ProtocolEnum>::from_id(0)
+                }
+            }
+
+            impl ProtocolEnum for $name {
+                fn from_id(id: i32) -> Self {
+                    match id {
+                        $($id => Self::$variant,)*
+                        id => Self::Unknown(id),
+                    }
+                }
+
+                fn id(self) -> i32 {
+                    match self {
+                        $(Self::$variant => $id,)*
+                        Self::Unknown(id) => id,
+                    }
+                }
+            }
+
+            impl MinecraftIo for $name {
+                fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+                    <$repr as EnumRepr>::from_id(self.id())?.minecraft_write(writer)
+                }
+
+                fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+                    Ok(<Self as ProtocolEnum>::from_id(<$repr>::minecraft_read(reader)?.into_id()))
+                }
+            }
+        )*
+    };
+}
+
+/// An enum sent as a byte in a packet where it is usually a VarInt.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AsByte<E>(pub E);
+
+impl<E: ProtocolEnum> MinecraftIo for AsByte<E> {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        <u8 as EnumRepr>::from_id(self.0.id())?.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self(E::from_id(u8::minecraft_read(reader)? as i32)))
+    }
+}
+
+protocol_enums! {
+    Hand: VarInt {
+        Main = 0,
+        Off = 1,
+    }
+
+    /// The side of a block.
+    Facing: VarInt {
+        Down = 0,
+        Up = 1,
+        North = 2,
+        South = 3,
+        West = 4,
+        East = 5,
+    }
+
+    /// The direction a painting faces.
+    HorizontalFacing: i8 {
+        South = 0,
+        West = 1,
+        North = 2,
+        East = 3,
+    }
+
+    Pose: VarInt {
+        Standing = 0,
+        FallFlying = 1,
+        Sleeping = 2,
+        Swimming = 3,
+        SpinAttack = 4,
+        Crouching = 5,
+        LongJumping = 6,
+        Dying = 7,
+    }
+
+    GameMode: VarInt {
+        Survival = 0,
+        Creative = 1,
+        Adventure = 2,
+        Spectator = 3,
+    }
+
+    /// The game mode a player was in before their current one.
+    PreviousGameMode: i8 {
+        None = -1,
+        Survival = 0,
+        Creative = 1,
+        Adventure = 2,
+        Spectator = 3,
+    }
+
+    Difficulty: u8 {
+        Peaceful = 0,
+        Easy = 1,
+        Normal = 2,
+        Hard = 3,
+    }
+
+    /// Where a chat message is shown.
+    ChatPosition: u8 {
+        Chat = 0,
+        System = 1,
+        GameInfo = 2,
+    }
+
+    SoundCategory: VarInt {
+        Master = 0,
+        Music = 1,
+        Records = 2,
+        Weather = 3,
+        Blocks = 4,
+        Hostile = 5,
+        Neutral = 6,
+        Players = 7,
+        Ambient = 8,
+        Voice = 9,
+    }
+
+    GameStateChange: u8 {
+        NoRespawnBlockAvailable = 0,
+        EndRaining = 1,
+        BeginRaining = 2,
+        ChangeGameMode = 3,
+        WinGame = 4,
+        DemoEvent = 5,
+        ArrowHitPlayer = 6,
+        RainLevelChange = 7,
+        ThunderLevelChange = 8,
+        PufferfishSting = 9,
+        ElderGuardianAppearance = 10,
+        EnableRespawnScreen = 11,
+    }
+
+    /// Which point of the player looks at a target.
+    LookAnchor: VarInt {
+        Feet = 0,
+        Eyes = 1,
+    }
+
+    ObjectiveType: VarInt {
+        Integer = 0,
+        Hearts = 1,
+    }
+
+    /// A team's color, sent as a chat formatting ID.
+    TeamColor: VarInt {
+        Black = 0,
+        DarkBlue = 1,
+        DarkGreen = 2,
+        DarkAqua = 3,
+        DarkRed = 4,
+        DarkPurple = 5,
+        Gold = 6,
+        Gray = 7,
+        DarkGray = 8,
+        Blue = 9,
+        Green = 10,
+        Aqua = 11,
+        Red = 12,
+        LightPurple = 13,
+        Yellow = 14,
+        White = 15,
+        Obfuscated = 16,
+        Bold = 17,
+        Strikethrough = 18,
+        Underlined = 19,
+        Italic = 20,
+        Reset = 21,
+    }
+
+    /// How an advancement is shown.
+    AdvancementFrame: VarInt {
+        Task = 0,
+        Challenge = 1,
+        Goal = 2,
+    }
+
+    /// How an attribute modifier's amount is applied.
+    ModifierOperation: i8 {
+        Add = 0,
+        AddMultipliedBase = 1,
+        MultiplyTotal = 2,
+    }
+
+    ClientStatusAction: VarInt {
+        PerformRespawn = 0,
+        RequestStats = 1,
+    }
+
+    ChatMode: VarInt {
+        Enabled = 0,
+        CommandsOnly = 1,
+        Hidden = 2,
+    }
+
+    MainHand: VarInt {
+        Left = 0,
+        Right = 1,
+    }
+
+    InteractionType: VarInt {
+        Interact = 0,
+        Attack = 1,
+        InteractAt = 2,
+    }
+
+    DiggingStatus: VarInt {
+        StartedDigging = 0,
+        CancelledDigging = 1,
+        FinishedDigging = 2,
+        DropItemStack = 3,
+        DropItem = 4,
+        ShootArrowOrFinishEating = 5,
+        SwapItemInHand = 6,
+    }
+
+    PlayerAction: VarInt {
+        StartSneaking = 0,
+        StopSneaking = 1,
+        LeaveBed = 2,
+        StartSprinting = 3,
+        StopSprinting = 4,
+        StartHorseJump = 5,
+        StopHorseJump = 6,
+        OpenHorseInventory = 7,
+        StartElytraFlying = 8,
+    }
+
+    RecipeBookType: VarInt {
+        Crafting = 0,
+        Furnace = 1,
+        BlastFurnace = 2,
+        Smoker = 3,
+    }
+
+    ResourcePackResult: VarInt {
+        SuccessfullyLoaded = 0,
+        Declined = 1,
+        FailedDownload = 2,
+        Accepted = 3,
+    }
+
+    AdvancementTabAction: VarInt {
+        OpenedTab = 0,
+        ClosedScreen = 1,
+    }
+
+    CommandBlockMode: VarInt {
+        Sequence = 0,
+        Auto = 1,
+        Redstone = 2,
+    }
+
+    StructureBlockAction: VarInt {
+        UpdateData = 0,
+        SaveStructure = 1,
+        LoadStructure = 2,
+        DetectSize = 3,
+    }
+
+    StructureBlockMode: VarInt {
+        Save = 0,
+        Load = 1,
+        Corner = 2,
+        Data = 3,
+    }
+
+    Mirror: VarInt {
+        None = 0,
+        LeftRight = 1,
+        FrontBack = 2,
+    }
+
+    Rotation: VarInt {
+        None = 0,
+        Clockwise90 = 1,
+        Clockwise180 = 2,
+        Counterclockwise90 = 3,
+    }
+}
+
+impl TeamColor {
+    /// The color of team members' names, if this is a color rather than a
+    /// format.
+    pub fn color(self) -> Option<Color> {
+        // The first 16 IDs are the colors in legacy code order.
+        let code = char::from_digit(u32::try_from(self.id()).ok()?, 16)?;
+        Color::from_code(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enum_io() {
+        let mut vec = vec![];
+        DiggingStatus::FinishedDigging
+            .minecraft_write(&mut vec)
+            .unwrap();
+        Difficulty::Hard.minecraft_write(&mut vec).unwrap();
+        SoundCategory::Unknown(300)
+            .minecraft_write(&mut vec)
+            .unwrap();
+        AsByte(Facing::East).minecraft_write(&mut vec).unwrap();
+        assert_eq!(vec, &[2, 3, 0xac, 0x02, 5]);
+
+        let mut reader = &vec[..];
+        assert_eq!(
+            DiggingStatus::minecraft_read(&mut reader).unwrap(),
+            DiggingStatus::FinishedDigging
+        );
+        assert_eq!(
+            Difficulty::minecraft_read(&mut reader).unwrap(),
+            Difficulty::Hard
+        );
+        assert_eq!(
+            SoundCategory::minecraft_read(&mut reader).unwrap(),
+            SoundCategory::Unknown(300)
+        );
+        assert_eq!(
+            <AsByte<Facing>>::minecraft_read(&mut reader).unwrap(),
+            AsByte(Facing::East)
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_unknown() {
+        let mut vec = vec![];
+        Difficulty::Unknown(255).minecraft_write(&mut vec).unwrap();
+        HorizontalFacing::Unknown(-1)
+            .minecraft_write(&mut vec)
+            .unwrap();
+        assert_eq!(vec, &[255, 255]);
+
+        for error in [
+            Difficulty::Unknown(256).minecraft_write(&mut vec),
+            Difficulty::Unknown(-1).minecraft_write(&mut vec),
+            HorizontalFacing::Unknown(128).minecraft_write(&mut vec),
+            AsByte(Facing::Unknown(300)).minecraft_write(&mut vec),
+        ] {
+            assert!(error.unwrap_err().is::<EnumError>());
+        }
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    fn test_enum_ids() {
+        assert_eq!(Hand::default(), Hand::Main);
+        assert_eq!(TeamColor::from_id(12), TeamColor::Red);
+        assert_eq!(TeamColor::Reset.id(), 21);
+        assert_eq!(TeamColor::Red.color(), Some(Color::Red));
+        assert_eq!(TeamColor::Bold.color(), None);
+        assert_eq!(HorizontalFacing::from_id(-1), HorizontalFacing::Unknown(-1));
+        assert_eq!(HorizontalFacing::Unknown(-1).id(), -1);
+        assert_eq!(PreviousGameMode::from_id(-1), PreviousGameMode::None);
+    }
+}
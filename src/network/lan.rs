@@ -0,0 +1,208 @@
+use anyhow::Result;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+pub const LAN_MULTICAST_ADDRESS: Ipv4Addr = Ipv4Addr::new(224, 0, 2, 60);
+pub const LAN_PORT: u16 = 4445;
+
+/// Servers announce themselves every 1.5 seconds; one that has missed a few
+/// announcements is assumed to be gone.
+pub const DEFAULT_EXPIRY: Duration = Duration::from_secs(5);
+
+/// A world opened to LAN.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LanServer {
+    pub motd: String,
+    /// Where to connect: the announcer's IP with the port it advertised.
+    pub address: SocketAddr,
+    pub last_seen: Instant,
+}
+
+impl LanServer {
+    /// The host to pass to `connect_to_server`.
+    pub fn host(&self) -> String {
+        self.address.ip().to_string()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.address.port()
+    }
+}
+
+/// Splits a `[MOTD]...[/MOTD][AD]port[/AD]` announcement into its MOTD and
+/// port.
+pub fn parse_announcement(message: &str) -> Option<(String, u16)> {
+    let motd = between(message, "[MOTD]", "[/MOTD]")?;
+    let port = between(message, "[AD]", "[/AD]")?.trim().parse().ok()?;
+    Some((motd.to_owned(), port))
+}
+
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = text.split_once(start)?;
+    let (inside, _) = rest.split_once(end)?;
+    Some(inside)
+}
+
+/// Listens for LAN world announcements and keeps the set of servers that are
+/// currently announcing.
+pub struct LanDiscovery {
+    socket: UdpSocket,
+    /// Keyed by the address announcements come from.
+    servers: HashMap<SocketAddr, LanServer>,
+    expiry: Duration,
+}
+
+impl LanDiscovery {
+    /// Joins the vanilla LAN multicast group on every interface.
+    pub fn new() -> Result<Self> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        // Other clients on this machine listen on the same port.
+        socket.set_reuse_address(true)?;
+        let address = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, LAN_PORT);
+        socket.bind(&address.into())?;
+        socket.join_multicast_v4(&LAN_MULTICAST_ADDRESS, &Ipv4Addr::UNSPECIFIED)?;
+        Self::from_socket(socket.into())
+    }
+
+    /// Listens for announcements sent directly to `address` instead of the
+    /// multicast group.
+    pub fn bind(address: SocketAddr) -> Result<Self> {
+        Self::from_socket(UdpSocket::bind(address)?)
+    }
+
+    fn from_socket(socket: UdpSocket) -> Result<Self> {
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            servers: HashMap::new(),
+            expiry: DEFAULT_EXPIRY,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    pub fn set_expiry(&mut self, expiry: Duration) {
+        self.expiry = expiry;
+    }
+
+    /// Handles every announcement received since the last poll and forgets
+    /// servers that have stopped announcing. Never blocks.
+    pub fn poll(&mut self) -> Result<()> {
+        let mut buffer = [0u8; 1024];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((length, source)) => {
+                    let message = String::from_utf8_lossy(&buffer[..length]);
+                    if let Some((motd, port)) = parse_announcement(&message) {
+                        self.announce(source, motd, port, Instant::now());
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        self.expire(Instant::now());
+        Ok(())
+    }
+
+    fn announce(&mut self, source: SocketAddr, motd: String, port: u16, now: Instant) {
+        self.servers.insert(
+            source,
+            LanServer {
+                motd,
+                address: SocketAddr::new(source.ip(), port),
+                last_seen: now,
+            },
+        );
+    }
+
+    fn expire(&mut self, now: Instant) {
+        let expiry = self.expiry;
+        self.servers
+            .retain(|_, server| now.duration_since(server.last_seen) < expiry);
+    }
+
+    /// The servers heard from within the expiry time, in no particular order.
+    pub fn servers(&self) -> impl Iterator<Item = &LanServer> {
+        self.servers.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_parse_announcement() {
+        assert_eq!(
+            parse_announcement("[MOTD]Steve - New World[/MOTD][AD]51234[/AD]"),
+            Some(("Steve - New World".to_owned(), 51234))
+        );
+        assert_eq!(parse_announcement("[MOTD]No port[/MOTD]"), None);
+        assert_eq!(parse_announcement("[MOTD]x[/MOTD][AD]port[/AD]"), None);
+    }
+
+    /// Polls until `count` servers are known, since delivery over loopback
+    /// is not instant.
+    fn poll_until(discovery: &mut LanDiscovery, count: usize) {
+        for _ in 0..100 {
+            discovery.poll().unwrap();
+            if discovery.servers().count() == count {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("expected {count} servers");
+    }
+
+    #[test]
+    fn test_discovery() {
+        let mut discovery = LanDiscovery::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let target = discovery.local_addr().unwrap();
+
+        let first = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let second = UdpSocket::bind("127.0.0.1:0").unwrap();
+        first
+            .send_to(b"[MOTD]First[/MOTD][AD]40000[/AD]", target)
+            .unwrap();
+        first
+            .send_to(b"[MOTD]First again[/MOTD][AD]40000[/AD]", target)
+            .unwrap();
+        second
+            .send_to(b"[MOTD]Second[/MOTD][AD]40001[/AD]", target)
+            .unwrap();
+        second.send_to(b"not an announcement", target).unwrap();
+        poll_until(&mut discovery, 2);
+        // Give the duplicate a chance to arrive too.
+        thread::sleep(Duration::from_millis(50));
+        discovery.poll().unwrap();
+
+        let mut servers: Vec<_> = discovery.servers().cloned().collect();
+        servers.sort_by_key(|server| server.port());
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].motd, "First again");
+        assert_eq!(servers[0].host(), "127.0.0.1");
+        assert_eq!(servers[0].port(), 40000);
+        assert_eq!(servers[1].motd, "Second");
+        assert_eq!(servers[1].port(), 40001);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut discovery = LanDiscovery::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let now = Instant::now();
+        let source = "127.0.0.1:1".parse().unwrap();
+        discovery.announce(source, "World".to_owned(), 25565, now);
+
+        discovery.expire(now + DEFAULT_EXPIRY / 2);
+        assert_eq!(discovery.servers().count(), 1);
+        discovery.expire(now + DEFAULT_EXPIRY);
+        assert_eq!(discovery.servers().count(), 0);
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Where `build.rs` copies the `res/` directory.
+pub const RES_DIR: &str = concat!(env!("OUT_DIR"), "/res");
+
+pub const DEFAULT_LANGUAGE: &str = "en_us";
+
+/// A piece of a translation template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplatePart<'a> {
+    Literal(&'a str),
+    /// The translation argument at this index.
+    Argument(usize),
+}
+
+/// Splits a template into literal text and `%s`/`%1$s` arguments. `%%` is a
+/// literal `%`, and anything else after a `%` is kept as it is.
+pub fn parse_template(template: &str) -> Vec<TemplatePart<'_>> {
+    let mut parts = vec![];
+    let mut next_argument = 0;
+    let mut literal_start = 0;
+    let mut position = 0;
+    while let Some(found) = template[position..].find('%') {
+        let percent = position + found;
+        let after = &template[percent + 1..];
+        let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+        let index = after[..digits]
+            .parse::<usize>()
+            .ok()
+            .filter(|&index| index > 0);
+        let (part, length) = match index {
+            _ if after.starts_with('%') => (TemplatePart::Literal("%"), 2),
+            _ if after.starts_with('s') => {
+                next_argument += 1;
+                (TemplatePart::Argument(next_argument - 1), 2)
+            }
+            Some(index) if after[digits..].starts_with("$s") => {
+                (TemplatePart::Argument(index - 1), digits + 3)
+            }
+            _ => {
+                position = percent + 1;
+                continue;
+            }
+        };
+        if literal_start < percent {
+            parts.push(TemplatePart::Literal(&template[literal_start..percent]));
+        }
+        parts.push(part);
+        position = percent + length;
+        literal_start = position;
+    }
+    if literal_start < template.len() {
+        parts.push(TemplatePart::Literal(&template[literal_start..]));
+    }
+    parts
+}
+
+/// Translations from a Minecraft language file, like `en_us.json`.
+#[derive(Clone, Debug, Default)]
+pub struct Language {
+    translations: HashMap<String, String>,
+}
+
+impl Language {
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(Self {
+            translations: serde_json::from_str(json)?,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Loads `lang/<code>.json` from the resources copied by the build.
+    pub fn load_resource(code: &str) -> Result<Self> {
+        Self::load(Path::new(RES_DIR).join("lang").join(format!("{code}.json")))
+    }
+
+    /// Loads `en_us`. Without it, chat shows translation keys.
+    pub fn load_default() -> Result<Self> {
+        Self::load_resource(DEFAULT_LANGUAGE)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.translations.get(key).map(String::as_str)
+    }
+
+    /// The translation of `key`, or the key itself if there is none.
+    pub fn template<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get(key).unwrap_or(key)
+    }
+
+    /// Translates `key` with the arguments substituted in. Arguments that
+    /// were not given are left as written.
+    pub fn translate(&self, key: &str, arguments: &[&str]) -> String {
+        let template = self.template(key);
+        let mut text = String::new();
+        for part in parse_template(template) {
+            match part {
+                TemplatePart::Literal(literal) => text.push_str(literal),
+                TemplatePart::Argument(index) => match arguments.get(index) {
+                    Some(argument) => text.push_str(argument),
+                    None => text.push_str(&format!("%{}$s", index + 1)),
+                },
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::chat::{ChatComponent, Color};
+    use crate::network::Chat;
+
+    fn language() -> Language {
+        Language::from_json(
+            r#"{
+                "chat.type.text": "<%s> %s",
+                "death.attack.arrow.item": "%1$s was shot by %2$s using %3$s",
+                "commands.swap": "%2$s before %1$s",
+                "gui.percent": "100%%"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_template() {
+        assert_eq!(
+            parse_template("<%s> %s"),
+            [
+                TemplatePart::Literal("<"),
+                TemplatePart::Argument(0),
+                TemplatePart::Literal("> "),
+                TemplatePart::Argument(1),
+            ]
+        );
+        assert_eq!(
+            parse_template("%2$s%1$s"),
+            [TemplatePart::Argument(1), TemplatePart::Argument(0)]
+        );
+        assert_eq!(
+            parse_template("50%% %d"),
+            [
+                TemplatePart::Literal("50"),
+                TemplatePart::Literal("%"),
+                TemplatePart::Literal(" %d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_translate() {
+        let language = language();
+        assert_eq!(
+            language.translate("chat.type.text", &["Notch", "hi"]),
+            "<Notch> hi"
+        );
+        assert_eq!(
+            language.translate("commands.swap", &["a", "b"]),
+            "b before a"
+        );
+        assert_eq!(language.translate("gui.percent", &[]), "100%");
+        assert_eq!(
+            language.translate("chat.type.text", &["Notch"]),
+            "<Notch> %2$s"
+        );
+        assert_eq!(language.translate("missing.key", &["x"]), "missing.key");
+    }
+
+    #[test]
+    fn test_translate_component() {
+        let language = language();
+        let mut name = ChatComponent::text("Skeleton");
+        name.style.color = Some(Color::Red);
+        let component = ChatComponent::translate(
+            "death.attack.arrow.item",
+            vec![
+                ChatComponent::text("Steve"),
+                name,
+                ChatComponent::translate("item.minecraft.bow", vec![]),
+            ],
+        );
+        assert_eq!(
+            component.to_plain_in(&language),
+            "Steve was shot by Skeleton using item.minecraft.bow"
+        );
+        assert_eq!(
+            component.to_ansi_in(&language),
+            "Steve was shot by \x1b[91mSkeleton\x1b[0m using item.minecraft.bow"
+        );
+
+        let chat = Chat(r#"{"translate":"chat.type.text","with":["Notch","hi"]}"#.to_owned());
+        assert_eq!(chat.to_plain_in(&language), "<Notch> hi");
+        assert_eq!(
+            Chat("not json".to_owned()).to_plain_in(&language),
+            "not json"
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let path = std::env::temp_dir().join(format!("mc-lang-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"multiplayer.player.joined": "%s joined the game"}"#,
+        )
+        .unwrap();
+        let language = Language::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            language.translate("multiplayer.player.joined", &["Alex"]),
+            "Alex joined the game"
+        );
+        assert!(Language::load_resource("xx_xx").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::network::status::{open_stream, Players, ServerStatus, Version};
+use crate::network::Chat;
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::time::Instant;
+
+/// The protocol version sent in the ping, that of 1.6.4. Older servers ignore
+/// everything after the first two bytes.
+const LEGACY_PROTOCOL_VERSION: u8 = 78;
+
+const KICK_PACKET_ID: u8 = 0xff;
+
+#[derive(Clone, Debug)]
+pub enum LegacyPingError {
+    UnexpectedPacket(u8),
+    Malformed(String),
+}
+
+impl Display for LegacyPingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for LegacyPingError {}
+
+/// Asks a pre-Netty server (or a proxy that only speaks the old protocol) for
+/// its status with the `0xFE 0x01` server list ping.
+pub fn legacy_ping(address: &str, port: u16) -> Result<ServerStatus> {
+    let mut request = vec![];
+    write_legacy_request(&mut request, address, port)?;
+
+    let mut stream = open_stream(address, port)?;
+    let start = Instant::now();
+    stream.write_all(&request)?;
+    let response = read_legacy_response(&mut stream)?;
+    let latency = start.elapsed();
+
+    let mut status = parse_legacy_response(&response)?;
+    status.latency = latency;
+    Ok(status)
+}
+
+fn write_utf16(writer: &mut impl Write, text: &str) -> Result<()> {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    writer.write_u16::<BigEndian>(units.len() as u16)?;
+    for unit in units {
+        writer.write_u16::<BigEndian>(unit)?;
+    }
+    Ok(())
+}
+
+/// Writes the 1.6 form of the ping: `0xFE 0x01` followed by the
+/// `MC|PingHost` plugin message naming the server being pinged.
+pub fn write_legacy_request(writer: &mut impl Write, address: &str, port: u16) -> Result<()> {
+    writer.write_all(&[0xfe, 0x01, 0xfa])?;
+    write_utf16(writer, "MC|PingHost")?;
+
+    let mut data = vec![LEGACY_PROTOCOL_VERSION];
+    write_utf16(&mut data, address)?;
+    data.write_i32::<BigEndian>(port as i32)?;
+    writer.write_u16::<BigEndian>(data.len() as u16)?;
+    writer.write_all(&data)?;
+    Ok(())
+}
+
+/// Reads the kick packet the server answers with and returns its reason
+/// string, which carries the status.
+pub fn read_legacy_response(reader: &mut impl Read) -> Result<String> {
+    let packet_id = reader.read_u8()?;
+    if packet_id != KICK_PACKET_ID {
+        Err(LegacyPingError::UnexpectedPacket(packet_id))?;
+    }
+    let length = reader.read_u16::<BigEndian>()?;
+    let mut units = vec![0u16; length as usize];
+    reader.read_u16_into::<BigEndian>(&mut units)?;
+    Ok(String::from_utf16(&units)?)
+}
+
+/// Parses the kick string of either format: the 1.4+ `§1\0`-prefixed, NUL
+/// separated fields, or the older `motd§online§max`.
+pub fn parse_legacy_response(response: &str) -> Result<ServerStatus> {
+    let malformed = || LegacyPingError::Malformed(response.to_owned());
+
+    let (version, motd, online, max) = match response.strip_prefix("§1\0") {
+        Some(fields) => {
+            let fields: Vec<&str> = fields.split('\0').collect();
+            let [protocol, name, motd, online, max] = fields[..] else {
+                Err(malformed())?
+            };
+            let version = Version {
+                name: name.to_owned(),
+                protocol: protocol.parse().map_err(|_| malformed())?,
+            };
+            (version, motd, online, max)
+        }
+        None => {
+            // The MOTD may itself contain formatting codes, so split from the
+            // end.
+            let mut fields = response.rsplitn(3, '§');
+            let max = fields.next().ok_or_else(malformed)?;
+            let online = fields.next().ok_or_else(malformed)?;
+            let motd = fields.next().ok_or_else(malformed)?;
+            (Version::default(), motd, online, max)
+        }
+    };
+
+    Ok(ServerStatus {
+        version,
+        players: Players {
+            max: max.parse().map_err(|_| malformed())?,
+            online: online.parse().map_err(|_| malformed())?,
+            sample: vec![],
+        },
+        description: Chat(serde_json::Value::String(motd.to_owned()).to_string()),
+        favicon: None,
+        latency: Default::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::status::ping;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn kick(reason: &str) -> Vec<u8> {
+        let mut packet = vec![KICK_PACKET_ID];
+        write_utf16(&mut packet, reason).unwrap();
+        packet
+    }
+
+    #[test]
+    fn test_write_legacy_request() {
+        let mut request = vec![];
+        write_legacy_request(&mut request, "a", 25565).unwrap();
+        assert_eq!(
+            request,
+            [
+                0xfe, 0x01, 0xfa, 0, 11, 0, b'M', 0, b'C', 0, b'|', 0, b'P', 0, b'i', 0, b'n', 0,
+                b'g', 0, b'H', 0, b'o', 0, b's', 0, b't', 0, 9, 78, 0, 1, 0, b'a', 0, 0, 0x63,
+                0xdd
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_legacy_response() {
+        let packet = kick("§1\u{0}127\u{0}1.6.4\u{0}§aHi\u{0}3\u{0}20");
+        let response = read_legacy_response(&mut &packet[..]).unwrap();
+        assert_eq!(response, "§1\u{0}127\u{0}1.6.4\u{0}§aHi\u{0}3\u{0}20");
+
+        assert!(read_legacy_response(&mut &[0x02, 0, 0][..]).is_err());
+    }
+
+    #[test]
+    fn test_parse_1_4_response() {
+        let status =
+            parse_legacy_response("§1\u{0}78\u{0}1.6.4\u{0}A Minecraft Server\u{0}3\u{0}20")
+                .unwrap();
+        assert_eq!(status.version.protocol, 78);
+        assert_eq!(status.version.name, "1.6.4");
+        assert_eq!(status.players.online, 3);
+        assert_eq!(status.players.max, 20);
+        assert_eq!(status.description, r#""A Minecraft Server""#);
+    }
+
+    #[test]
+    fn test_parse_beta_response() {
+        let status = parse_legacy_response("§cOld §lserver§0§20").unwrap();
+        assert_eq!(status.version, Version::default());
+        assert_eq!(status.players.online, 0);
+        assert_eq!(status.players.max, 20);
+        assert_eq!(status.description, r#""§cOld §lserver""#);
+    }
+
+    #[test]
+    fn test_parse_malformed_response() {
+        assert!(parse_legacy_response("§1\u{0}78\u{0}1.6.4").is_err());
+        assert!(parse_legacy_response("no separators").is_err());
+    }
+
+    #[test]
+    fn test_ping_falls_back_to_legacy() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            // Like a pre-Netty server, drop the connection on a packet it
+            // does not understand.
+            let (modern, _) = listener.accept().unwrap();
+            drop(modern);
+
+            let (mut legacy, _) = listener.accept().unwrap();
+            let mut request = [0; 54];
+            legacy.read_exact(&mut request).unwrap();
+            assert_eq!(request[..3], [0xfe, 0x01, 0xfa]);
+            legacy
+                .write_all(&kick("§1\u{0}78\u{0}1.6.4\u{0}Legacy\u{0}1\u{0}8"))
+                .unwrap();
+        });
+
+        let status = ping("127.0.0.1", port).unwrap();
+        assert_eq!(status.version.name, "1.6.4");
+        assert_eq!(status.players.max, 8);
+        assert_eq!(status.description, r#""Legacy""#);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+use crate::network::packets::{ChunkDataAndUpdateLight, UpdateLight};
+use crate::network::{BitSet, MinecraftIo, VarInt};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+/// Bytes in a section's light array: one nibble for each of 16³ blocks.
+pub const LIGHT_ARRAY_LENGTH: usize = 2048;
+
+#[derive(Copy, Clone, Debug)]
+pub enum LightError {
+    InvalidLength(i32),
+    ArrayCountMismatch { mask: usize, arrays: usize },
+}
+
+impl Display for LightError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for LightError {}
+
+/// The sky or block light of one 16×16×16 section, a level from 0 to 15 per
+/// block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LightArray(pub Box<[u8; LIGHT_ARRAY_LENGTH]>);
+
+impl Default for LightArray {
+    fn default() -> Self {
+        Self(Box::new([0; LIGHT_ARRAY_LENGTH]))
+    }
+}
+
+impl LightArray {
+    /// Blocks are ordered by y, then z, then x, and the even block of each
+    /// pair is in the low nibble.
+    fn indexof(x: usize, y: usize, z: usize) -> usize {
+        assert!(x < 16 && y < 16 && z < 16);
+        y << 8 | z << 4 | x
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        let index = Self::indexof(x, y, z);
+        (self.0[index / 2] >> (index % 2 * 4)) & 0xf
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        let index = Self::indexof(x, y, z);
+        let shift = index % 2 * 4;
+        let byte = &mut self.0[index / 2];
+        *byte = *byte & !(0xf << shift) | (level & 0xf) << shift;
+    }
+}
+
+impl MinecraftIo for LightArray {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        VarInt(LIGHT_ARRAY_LENGTH as i32).minecraft_write(writer)?;
+        writer.write_all(&self.0[..])?;
+        Ok(())
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let length = VarInt::read(reader)?;
+        if length.0 as usize != LIGHT_ARRAY_LENGTH {
+            Err(LightError::InvalidLength(length.0))?;
+        }
+        let mut array = Self::default();
+        reader.read_exact(&mut array.0[..])?;
+        Ok(array)
+    }
+}
+
+/// The light of a chunk column, keyed by mask bit. Bit 0 is the section just
+/// below the world's lowest one, so a section at index `i` from the bottom of
+/// the world has bit `i + 1`. Sections a packet says nothing about are absent.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChunkLight {
+    pub sky: BTreeMap<usize, LightArray>,
+    pub block: BTreeMap<usize, LightArray>,
+}
+
+impl ChunkLight {
+    pub fn decode(
+        sky_light_mask: &BitSet,
+        empty_sky_light_mask: &BitSet,
+        sky_light_array: &[LightArray],
+        block_light_mask: &BitSet,
+        empty_block_light_mask: &BitSet,
+        block_light_array: &[LightArray],
+    ) -> Result<Self> {
+        Ok(Self {
+            sky: decode_layer(sky_light_mask, empty_sky_light_mask, sky_light_array)?,
+            block: decode_layer(block_light_mask, empty_block_light_mask, block_light_array)?,
+        })
+    }
+
+    pub fn sky_light(&self, bit: usize) -> Option<&LightArray> {
+        self.sky.get(&bit)
+    }
+
+    pub fn block_light(&self, bit: usize) -> Option<&LightArray> {
+        self.block.get(&bit)
+    }
+
+    /// Replaces the sections `update` has light for, as an `UpdateLight` for
+    /// an already loaded chunk does.
+    pub fn apply(&mut self, update: ChunkLight) {
+        self.sky.extend(update.sky);
+        self.block.extend(update.block);
+    }
+}
+
+/// Pairs each set mask bit with the next array, and gives sections in the
+/// empty mask an all-zero array.
+fn decode_layer(
+    mask: &BitSet,
+    empty_mask: &BitSet,
+    arrays: &[LightArray],
+) -> Result<BTreeMap<usize, LightArray>> {
+    if mask.count() != arrays.len() {
+        Err(LightError::ArrayCountMismatch {
+            mask: mask.count(),
+            arrays: arrays.len(),
+        })?;
+    }
+    let mut layer: BTreeMap<usize, LightArray> = empty_mask
+        .iter()
+        .map(|bit| (bit, LightArray::default()))
+        .collect();
+    layer.extend(mask.iter().zip(arrays.iter().cloned()));
+    Ok(layer)
+}
+
+impl ChunkDataAndUpdateLight {
+    pub fn light(&self) -> Result<ChunkLight> {
+        ChunkLight::decode(
+            &self.sky_light_mask,
+            &self.empty_sky_light_mask,
+            &self.sky_light_array,
+            &self.block_light_mask,
+            &self.empty_block_light_mask,
+            &self.block_light_array,
+        )
+    }
+}
+
+impl UpdateLight {
+    pub fn light(&self) -> Result<ChunkLight> {
+        ChunkLight::decode(
+            &self.sky_light_mask,
+            &self.empty_sky_light_mask,
+            &self.sky_light_array,
+            &self.block_light_mask,
+            &self.empty_block_light_mask,
+            &self.block_light_array,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Direction, Packet, State};
+
+    fn filled(level: u8) -> LightArray {
+        LightArray(Box::new([level << 4 | level; LIGHT_ARRAY_LENGTH]))
+    }
+
+    #[test]
+    fn test_light_array_nibbles() {
+        let mut array = LightArray::default();
+        array.set(1, 0, 0, 15);
+        array.set(0, 1, 2, 7);
+        assert_eq!(array.0[0], 0xf0);
+        assert_eq!(array.0[(256 + 32) / 2], 0x07);
+        assert_eq!(array.get(1, 0, 0), 15);
+        assert_eq!(array.get(0, 0, 0), 0);
+        assert_eq!(array.get(0, 1, 2), 7);
+        array.set(1, 0, 0, 3);
+        assert_eq!(array.get(1, 0, 0), 3);
+    }
+
+    #[test]
+    fn test_update_light_round_trip() {
+        let packet = Packet::UpdateLight(UpdateLight {
+            chunk_x: VarInt(-2),
+            chunk_z: VarInt(5),
+            trust_edges: true,
+            sky_light_mask: [1, 3].into_iter().collect(),
+            block_light_mask: [70].into_iter().collect(),
+            empty_sky_light_mask: [0].into_iter().collect(),
+            empty_block_light_mask: BitSet::new(),
+            sky_light_array: vec![filled(15), filled(4)],
+            block_light_array: vec![filled(9)],
+        });
+        let mut payload = vec![];
+        packet.write_payload(&mut payload).unwrap();
+
+        let Packet::UpdateLight(update) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected UpdateLight");
+        };
+        assert_eq!(update.block_light_mask.0, [0, 1 << 6]);
+
+        let light = update.light().unwrap();
+        assert_eq!(light.sky.keys().copied().collect::<Vec<_>>(), [0, 1, 3]);
+        assert_eq!(light.sky_light(0).unwrap().get(5, 5, 5), 0);
+        assert_eq!(light.sky_light(1).unwrap().get(5, 5, 5), 15);
+        assert_eq!(light.sky_light(3).unwrap().get(0, 15, 0), 4);
+        assert!(light.sky_light(2).is_none());
+        assert_eq!(light.block_light(70).unwrap().get(15, 15, 15), 9);
+    }
+
+    #[test]
+    fn test_light_errors() {
+        let mask: BitSet = [0, 1].into_iter().collect();
+        let result = ChunkLight::decode(
+            &mask,
+            &BitSet::new(),
+            &[filled(1)],
+            &BitSet::new(),
+            &BitSet::new(),
+            &[],
+        );
+        assert!(result.is_err());
+
+        let short = [4, 0, 0, 0, 0];
+        assert!(LightArray::minecraft_read(&mut &short[..]).is_err());
+    }
+}
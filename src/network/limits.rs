@@ -0,0 +1,117 @@
+use crate::network::packets::MAX_FRAME_LENGTH;
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LimitError {
+    StringTooLong { length: usize, max: usize },
+    ArrayTooLong { length: usize, max: usize },
+    NbtTooDeep(usize),
+    NbtTooLarge(usize),
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for LimitError {}
+
+/// The most a decoded packet may hold, so a hostile server cannot make the
+/// client allocate more than it sends. Decoding uses the limits current on
+/// its thread; see [`Limits::scope`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Code points in a string. Fields the protocol limits further, like
+    /// usernames, are held to the smaller of the two.
+    pub max_string_length: usize,
+    /// Code points in a JSON chat component.
+    pub max_chat_length: usize,
+    /// Elements in a length-prefixed array, bytes included.
+    pub max_array_length: usize,
+    /// Bytes in a frame as sent, after the length prefix.
+    pub max_frame_length: usize,
+    /// Bytes in a payload after decompression.
+    pub max_payload_length: usize,
+    /// Compounds and lists nested in one NBT tag.
+    pub max_nbt_depth: usize,
+    /// Bytes in one NBT tag.
+    pub max_nbt_size: usize,
+}
+
+impl Limits {
+    /// The limits the vanilla server and client enforce.
+    pub const DEFAULT: Self = Self {
+        max_string_length: 32767,
+        max_chat_length: 262144,
+        max_array_length: 1 << 21,
+        max_frame_length: MAX_FRAME_LENGTH,
+        max_payload_length: 1 << 23,
+        max_nbt_depth: 512,
+        max_nbt_size: 1 << 21,
+    };
+
+    pub fn current() -> Self {
+        CURRENT.with(Cell::get)
+    }
+
+    /// Runs `f` with these as the current limits on this thread.
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        struct Restore(Limits);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|current| current.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CURRENT.with(|current| current.replace(self)));
+        f()
+    }
+
+    /// These limits with strings held to at most `max` code points.
+    pub fn with_max_string_length(self, max: usize) -> Self {
+        Self {
+            max_string_length: self.max_string_length.min(max),
+            ..self
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+thread_local! {
+    static CURRENT: Cell<Limits> = const { Cell::new(Limits::DEFAULT) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope() {
+        let small = Limits {
+            max_array_length: 4,
+            ..Limits::DEFAULT
+        };
+        let inner = small.scope(|| {
+            assert_eq!(Limits::current(), small);
+            Limits::current()
+                .with_max_string_length(16)
+                .scope(Limits::current)
+        });
+        assert_eq!(inner.max_string_length, 16);
+        assert_eq!(inner.max_array_length, 4);
+        assert_eq!(Limits::current(), Limits::DEFAULT);
+        assert_eq!(
+            Limits::DEFAULT.with_max_string_length(1 << 20),
+            Limits::DEFAULT
+        );
+    }
+}
@@ -0,0 +1,358 @@
+use crate::network::compression::{compress_payload, decompress_payload_into};
+use crate::network::encryption::{cipher_pair, Decryptor, Encryptor, SharedSecret};
+use crate::network::limits::Limits;
+use crate::network::packets::{self, write_frame_bytes, FrameError, PacketRef};
+use crate::network::varint::VarIntError;
+use crate::network::{Direction, Packet, State, VarInt};
+use anyhow::Result;
+
+/// The protocol without any I/O. Bytes read from the peer go in with
+/// `receive` and come out as packets; packets to send go in with `send` and
+/// come out as bytes to write. The connection state, compression and
+/// encryption are tracked from the packets passing through, so the same
+/// machine can be driven by a blocking thread, a non-blocking poll loop or a
+/// test replaying recorded bytes.
+pub struct ProtocolMachine {
+    /// The direction of the packets this end of the connection receives.
+    direction: Direction,
+    state: State,
+    /// Set once compression has been negotiated; payloads of at least this
+    /// many bytes are sent zlib compressed.
+    compression_threshold: Option<i32>,
+    /// What received packets may contain.
+    limits: Limits,
+    encryptor: Option<Encryptor>,
+    decryptor: Option<Decryptor>,
+
+    /// Decrypted bytes received, of which the first `position` have been
+    /// decoded.
+    received: Vec<u8>,
+    position: usize,
+    /// The payload decompressed from the last frame, kept to reuse its
+    /// allocation.
+    payload: Vec<u8>,
+    /// Encrypted bytes waiting to be written.
+    outgoing: Vec<u8>,
+}
+
+impl ProtocolMachine {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            state: State::Handshake,
+            compression_threshold: None,
+            limits: Limits::DEFAULT,
+            encryptor: None,
+            decryptor: None,
+
+            received: Vec::new(),
+            position: 0,
+            payload: Vec::new(),
+            outgoing: Vec::new(),
+        }
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    pub fn compression_threshold(&self) -> Option<i32> {
+        self.compression_threshold
+    }
+
+    /// Switches framing to the compressed format. A negative threshold turns
+    /// compression back off, matching the meaning of `SetCompression`.
+    pub fn set_compression(&mut self, threshold: Option<i32>) {
+        self.compression_threshold = threshold.filter(|&t| t >= 0);
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryptor.is_some()
+    }
+
+    /// Encrypts everything sent and received from now on with AES/CFB8 keyed
+    /// with `shared_secret`. Bytes already received but not yet decoded were
+    /// sent after the peer switched too, so they are decrypted as well.
+    pub fn enable_encryption(&mut self, shared_secret: &SharedSecret) {
+        let (encryptor, mut decryptor) = cipher_pair(shared_secret);
+        decryptor.decrypt(&mut self.received[self.position..]);
+        self.encryptor = Some(encryptor);
+        self.decryptor = Some(decryptor);
+    }
+
+    /// Adds bytes read from the peer.
+    pub fn receive(&mut self, bytes: &[u8]) {
+        self.received.drain(..self.position);
+        self.position = 0;
+        let start = self.received.len();
+        self.received.extend_from_slice(bytes);
+        if let Some(decryptor) = &mut self.decryptor {
+            decryptor.decrypt(&mut self.received[start..]);
+        }
+    }
+
+    /// Finds the next whole frame received, returning where its contents
+    /// start and end, or `None` if more bytes are needed.
+    fn next_frame(&self) -> Result<Option<(usize, usize)>> {
+        let mut bytes = &self.received[self.position..];
+        let length = match VarInt::decode(&mut bytes) {
+            Ok(length) => length,
+            Err(error) => {
+                return match error.downcast_ref::<VarIntError>() {
+                    Some(VarIntError::NotEnoughBytes) => Ok(None),
+                    _ => Err(error),
+                }
+            }
+        };
+        if length.0 < 0 || length.0 as usize > self.limits.max_frame_length {
+            Err(FrameError::InvalidLength(length.0))?;
+        }
+        if bytes.len() < length.0 as usize {
+            return Ok(None);
+        }
+        let start = self.received.len() - bytes.len();
+        Ok(Some((start, start + length.0 as usize)))
+    }
+
+    /// Whether a whole packet has been received.
+    pub fn has_packet(&self) -> Result<bool> {
+        Ok(self.next_frame()?.is_some())
+    }
+
+    /// Decodes the next packet received, or returns `None` if more bytes are
+    /// needed.
+    pub fn next_packet(&mut self) -> Result<Option<Packet>> {
+        Ok(self.next_packet_ref()?.map(PacketRef::into_owned))
+    }
+
+    /// Like `next_packet`, but borrows the large fields of the packets
+    /// [`PacketRef`] supports from the received bytes.
+    pub fn next_packet_ref(&mut self) -> Result<Option<PacketRef<'_>>> {
+        let Some((start, end)) = self.next_frame()? else {
+            return Ok(None);
+        };
+        self.position = end;
+        let limits = self.limits;
+        let payload = match self.compression_threshold {
+            None => &self.received[start..end],
+            Some(_) => {
+                limits.scope(|| {
+                    decompress_payload_into(&self.received[start..end], &mut self.payload)
+                })?;
+                &self.payload[..]
+            }
+        };
+
+        let id = VarInt::decode(&mut &payload[..])?;
+        if !PacketRef::borrows(self.state, self.direction, id) {
+            let packet =
+                limits.scope(|| Packet::read_payload(self.state, self.direction, payload))?;
+            self.observe(&packet);
+            return Ok(Some(PacketRef::Packet(packet)));
+        }
+        let packet = match self.compression_threshold {
+            None => &self.received[start..end],
+            Some(_) => &self.payload[..],
+        };
+        Ok(Some(limits.scope(|| {
+            PacketRef::read_payload(self.state, self.direction, packet)
+        })?))
+    }
+
+    /// Encodes `packet` to be sent, applying any change to the connection
+    /// it implies once it is queued.
+    pub fn send(&mut self, packet: &Packet) -> Result<()> {
+        let mut payload = vec![];
+        packet.write_payload(&mut payload)?;
+        let start = self.outgoing.len();
+        match self.compression_threshold {
+            None => write_frame_bytes(&mut self.outgoing, &payload)?,
+            Some(threshold) => {
+                let frame = compress_payload(&payload, threshold)?;
+                write_frame_bytes(&mut self.outgoing, &frame)?;
+            }
+        }
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.encrypt(&mut self.outgoing[start..]);
+        }
+        self.observe(packet);
+        Ok(())
+    }
+
+    /// The bytes waiting to be written to the peer.
+    pub fn outgoing(&self) -> &[u8] {
+        &self.outgoing
+    }
+
+    /// Drops the first `count` outgoing bytes once they have been written.
+    pub fn consume_outgoing(&mut self, count: usize) {
+        self.outgoing.drain(..count);
+    }
+
+    pub fn take_outgoing(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Applies the state and framing changes a packet implies once it has
+    /// gone through the connection in either direction, so both ends of a
+    /// connection stay in step.
+    fn observe(&mut self, packet: &Packet) {
+        match packet {
+            Packet::Handshake(handshake) => match handshake.next_state {
+                VarInt(1) => self.state = State::Status,
+                VarInt(2) => self.state = State::Login,
+                _ => {}
+            },
+            Packet::SetCompression(packets::SetCompression { threshold }) => {
+                self.set_compression(Some(threshold.0))
+            }
+            Packet::LoginSuccess(_) => self.state = State::Play,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::packets::{
+        Handshake, KeepAliveRequest, LoginSuccess, ServerPluginMessage, SetCompression,
+    };
+    use crate::network::{ByteArray, Identifier};
+    use uuid::Uuid;
+
+    fn handshake() -> Packet {
+        Packet::Handshake(Handshake {
+            protocol_version: VarInt(758),
+            server_address: "localhost".to_owned(),
+            server_port: 25565,
+            next_state: VarInt(2),
+        })
+    }
+
+    #[test]
+    fn test_bytes_in_pieces() {
+        let mut client = ProtocolMachine::new(Direction::ClientBound);
+        let mut server = ProtocolMachine::new(Direction::ServerBound);
+        client.send(&handshake()).unwrap();
+        assert_eq!(client.state(), State::Login);
+        let bytes = client.take_outgoing();
+        assert_eq!(bytes, b"\x10\x00\xf6\x05\x09localhost\x63\xdd\x02");
+
+        for byte in &bytes[..bytes.len() - 1] {
+            server.receive(&[*byte]);
+            assert!(server.next_packet().unwrap().is_none());
+        }
+        server.receive(&bytes[bytes.len() - 1..]);
+        assert!(matches!(
+            server.next_packet().unwrap(),
+            Some(Packet::Handshake(_))
+        ));
+        assert_eq!(server.state(), State::Login);
+        assert!(server.next_packet().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_compression_and_encryption() {
+        let mut client = ProtocolMachine::new(Direction::ClientBound);
+        let mut server = ProtocolMachine::new(Direction::ServerBound);
+        client.set_state(State::Login);
+        server.set_state(State::Login);
+
+        let shared_secret = [7; 16];
+        server.enable_encryption(&shared_secret);
+        server
+            .send(&Packet::SetCompression(SetCompression {
+                threshold: VarInt(16),
+            }))
+            .unwrap();
+        server
+            .send(&Packet::LoginSuccess(LoginSuccess {
+                uuid: Uuid::from_u128(1),
+                usename: "tester".to_owned(),
+            }))
+            .unwrap();
+        server
+            .send(&Packet::ServerPluginMessage(ServerPluginMessage {
+                channel: Identifier("test:big".to_owned()),
+                data: ByteArray(vec![0x61; 500]),
+            }))
+            .unwrap();
+        assert_eq!(server.compression_threshold(), Some(16));
+        assert_eq!(server.state(), State::Play);
+        let bytes = server.take_outgoing();
+        assert!(bytes.len() < 500);
+
+        // The first packets arrive before the client has the secret, and are
+        // decrypted when it switches.
+        client.receive(&bytes[..10]);
+        client.enable_encryption(&shared_secret);
+        client.receive(&bytes[10..]);
+        assert!(matches!(
+            client.next_packet().unwrap(),
+            Some(Packet::SetCompression(_))
+        ));
+        assert!(matches!(
+            client.next_packet().unwrap(),
+            Some(Packet::LoginSuccess(_))
+        ));
+        assert_eq!(client.state(), State::Play);
+        match client.next_packet_ref().unwrap() {
+            Some(PacketRef::ServerPluginMessage(message)) => {
+                assert_eq!(message.channel, "test:big");
+                assert_eq!(message.data, [0x61; 500]);
+            }
+            packet => panic!("unexpected {packet:?}"),
+        }
+        assert!(client.next_packet().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_partial_writes() {
+        let mut server = ProtocolMachine::new(Direction::ServerBound);
+        server.set_state(State::Play);
+        for id in 0..3 {
+            server
+                .send(&Packet::KeepAliveRequest(KeepAliveRequest {
+                    keep_alive_id: id,
+                }))
+                .unwrap();
+        }
+        let mut client = ProtocolMachine::new(Direction::ClientBound);
+        client.set_state(State::Play);
+        while !server.outgoing().is_empty() {
+            let count = server.outgoing().len().min(4);
+            client.receive(&server.outgoing()[..count]);
+            server.consume_outgoing(count);
+        }
+        for id in 0..3 {
+            assert!(matches!(
+                client.next_packet().unwrap(),
+                Some(Packet::KeepAliveRequest(KeepAliveRequest { keep_alive_id })) if keep_alive_id == id
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_frame_length() {
+        let mut client = ProtocolMachine::new(Direction::ClientBound);
+        client.receive(&[0xff, 0xff, 0xff, 0x7f]);
+        assert!(client.next_packet().is_err());
+    }
+}
@@ -0,0 +1,532 @@
+use crate::network::enums::{Facing, Pose};
+use crate::network::read_write::{read_optional, write_optional};
+use crate::network::{Chat, Identifier, MinecraftIo, Nbt, Position, Slot, VarInt};
+use anyhow::Result;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use uuid::Uuid;
+
+/// The index that ends the metadata stream in place of another entry.
+const END_OF_METADATA: u8 = 0xff;
+
+/// Well known indices shared by every entity.
+pub const FLAGS_INDEX: u8 = 0;
+pub const CUSTOM_NAME_INDEX: u8 = 2;
+pub const POSE_INDEX: u8 = 6;
+
+/// Bits of the entity flags byte at index 0.
+pub const FLAG_ON_FIRE: u8 = 0x01;
+pub const FLAG_CROUCHING: u8 = 0x02;
+pub const FLAG_SPRINTING: u8 = 0x08;
+pub const FLAG_SWIMMING: u8 = 0x10;
+pub const FLAG_INVISIBLE: u8 = 0x20;
+pub const FLAG_GLOWING: u8 = 0x40;
+pub const FLAG_FLYING_WITH_ELYTRA: u8 = 0x80;
+
+/// Particles whose IDs are followed by extra data.
+pub const PARTICLE_BLOCK: i32 = 2;
+pub const PARTICLE_BLOCK_MARKER: i32 = 3;
+pub const PARTICLE_DUST: i32 = 14;
+pub const PARTICLE_DUST_COLOR_TRANSITION: i32 = 15;
+pub const PARTICLE_FALLING_DUST: i32 = 24;
+pub const PARTICLE_ITEM: i32 = 35;
+pub const PARTICLE_VIBRATION: i32 = 36;
+
+#[derive(Clone, Debug)]
+pub enum MetadataError {
+    UnknownType(i32),
+    UnknownVibrationDestination(String),
+}
+
+impl Display for MetadataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for MetadataError {}
+
+/// What a vibration particle travels towards.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VibrationDestination {
+    Block(Position),
+    Entity(VarInt),
+}
+
+/// The data that follows a particle ID, which depends on the particle.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ParticleData {
+    #[default]
+    None,
+    BlockState(VarInt),
+    Dust {
+        red: f32,
+        green: f32,
+        blue: f32,
+        scale: f32,
+    },
+    DustColorTransition {
+        from: [f32; 3],
+        scale: f32,
+        to: [f32; 3],
+    },
+    Item(Slot),
+    Vibration {
+        origin: (f64, f64, f64),
+        destination: VibrationDestination,
+        ticks: VarInt,
+    },
+}
+
+impl ParticleData {
+    pub fn read(particle_id: i32, reader: &mut impl Read) -> Result<Self> {
+        Ok(match particle_id {
+            PARTICLE_BLOCK | PARTICLE_BLOCK_MARKER | PARTICLE_FALLING_DUST => {
+                Self::BlockState(VarInt::minecraft_read(reader)?)
+            }
+            PARTICLE_DUST => Self::Dust {
+                red: f32::minecraft_read(reader)?,
+                green: f32::minecraft_read(reader)?,
+                blue: f32::minecraft_read(reader)?,
+                scale: f32::minecraft_read(reader)?,
+            },
+            PARTICLE_DUST_COLOR_TRANSITION => {
+                let (from_red, from_green, from_blue, scale, to_red, to_green, to_blue) =
+                    <(f32, f32, f32, f32, f32, f32, f32)>::minecraft_read(reader)?;
+                Self::DustColorTransition {
+                    from: [from_red, from_green, from_blue],
+                    scale,
+                    to: [to_red, to_green, to_blue],
+                }
+            }
+            PARTICLE_ITEM => Self::Item(Slot::minecraft_read(reader)?),
+            PARTICLE_VIBRATION => {
+                let origin = <(f64, f64, f64)>::minecraft_read(reader)?;
+                let kind = Identifier::minecraft_read(reader)?;
+                let destination = match kind.0.as_str() {
+                    "minecraft:block" => {
+                        VibrationDestination::Block(Position::minecraft_read(reader)?)
+                    }
+                    "minecraft:entity" => {
+                        VibrationDestination::Entity(VarInt::minecraft_read(reader)?)
+                    }
+                    _ => Err(MetadataError::UnknownVibrationDestination(kind.0))?,
+                };
+                Self::Vibration {
+                    origin,
+                    destination,
+                    ticks: VarInt::minecraft_read(reader)?,
+                }
+            }
+            _ => Self::None,
+        })
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> Result<()> {
+        match self {
+            Self::None => Ok(()),
+            Self::BlockState(state) => state.minecraft_write(writer),
+            Self::Dust {
+                red,
+                green,
+                blue,
+                scale,
+            } => (*red, *green, *blue, *scale).minecraft_write(writer),
+            Self::DustColorTransition { from, scale, to } => {
+                (from[0], from[1], from[2], *scale, to[0], to[1], to[2]).minecraft_write(writer)
+            }
+            Self::Item(slot) => slot.minecraft_write(writer),
+            Self::Vibration {
+                origin,
+                destination,
+                ticks,
+            } => {
+                origin.minecraft_write(writer)?;
+                match destination {
+                    VibrationDestination::Block(position) => {
+                        Identifier("minecraft:block".to_owned()).minecraft_write(writer)?;
+                        position.minecraft_write(writer)?;
+                    }
+                    VibrationDestination::Entity(entity_id) => {
+                        Identifier("minecraft:entity".to_owned()).minecraft_write(writer)?;
+                        entity_id.minecraft_write(writer)?;
+                    }
+                }
+                ticks.minecraft_write(writer)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle {
+    pub id: VarInt,
+    pub data: ParticleData,
+}
+
+impl MinecraftIo for Particle {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.id.minecraft_write(writer)?;
+        self.data.write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let id = VarInt::minecraft_read(reader)?;
+        let data = ParticleData::read(id.0, reader)?;
+        Ok(Self { id, data })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VillagerData {
+    pub villager_type: VarInt,
+    pub profession: VarInt,
+    pub level: VarInt,
+}
+
+/// One metadata value, tagged with its type on the wire.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+    Byte(i8),
+    VarInt(VarInt),
+    Float(f32),
+    String(String),
+    Chat(Chat),
+    OptionalChat(Option<Chat>),
+    Slot(Slot),
+    Boolean(bool),
+    Rotation(f32, f32, f32),
+    Position(Position),
+    OptionalPosition(Option<Position>),
+    Direction(Facing),
+    OptionalUuid(Option<Uuid>),
+    /// A block state, where 0 (air) means none.
+    BlockState(VarInt),
+    Nbt(Nbt),
+    Particle(Particle),
+    VillagerData(VillagerData),
+    /// Sent as the value plus one, with 0 meaning none.
+    OptionalVarInt(Option<VarInt>),
+    Pose(Pose),
+}
+
+impl MetadataValue {
+    pub fn type_id(&self) -> i32 {
+        match self {
+            Self::Byte(_) => 0,
+            Self::VarInt(_) => 1,
+            Self::Float(_) => 2,
+            Self::String(_) => 3,
+            Self::Chat(_) => 4,
+            Self::OptionalChat(_) => 5,
+            Self::Slot(_) => 6,
+            Self::Boolean(_) => 7,
+            Self::Rotation(..) => 8,
+            Self::Position(_) => 9,
+            Self::OptionalPosition(_) => 10,
+            Self::Direction(_) => 11,
+            Self::OptionalUuid(_) => 12,
+            Self::BlockState(_) => 13,
+            Self::Nbt(_) => 14,
+            Self::Particle(_) => 15,
+            Self::VillagerData(_) => 16,
+            Self::OptionalVarInt(_) => 17,
+            Self::Pose(_) => 18,
+        }
+    }
+}
+
+impl MinecraftIo for MetadataValue {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        VarInt(self.type_id()).minecraft_write(writer)?;
+        match self {
+            Self::Byte(value) => value.minecraft_write(writer),
+            Self::VarInt(value) => value.minecraft_write(writer),
+            Self::Float(value) => value.minecraft_write(writer),
+            Self::String(value) => value.minecraft_write(writer),
+            Self::Chat(value) => value.minecraft_write(writer),
+            Self::OptionalChat(value) => write_optional(value, writer),
+            Self::Slot(value) => value.minecraft_write(writer),
+            Self::Boolean(value) => value.minecraft_write(writer),
+            Self::Rotation(x, y, z) => (*x, *y, *z).minecraft_write(writer),
+            Self::Position(value) => value.minecraft_write(writer),
+            Self::OptionalPosition(value) => write_optional(value, writer),
+            Self::Direction(value) => value.minecraft_write(writer),
+            Self::OptionalUuid(value) => write_optional(value, writer),
+            Self::BlockState(value) => value.minecraft_write(writer),
+            Self::Nbt(value) => value.minecraft_write(writer),
+            Self::Particle(value) => value.minecraft_write(writer),
+            Self::VillagerData(value) => {
+                (value.villager_type, value.profession, value.level).minecraft_write(writer)
+            }
+            Self::OptionalVarInt(value) => {
+                VarInt(value.map_or(0, |value| value.0 + 1)).minecraft_write(writer)
+            }
+            Self::Pose(value) => value.minecraft_write(writer),
+        }
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let type_id = VarInt::minecraft_read(reader)?.0;
+        Ok(match type_id {
+            0 => Self::Byte(i8::minecraft_read(reader)?),
+            1 => Self::VarInt(VarInt::minecraft_read(reader)?),
+            2 => Self::Float(f32::minecraft_read(reader)?),
+            3 => Self::String(String::minecraft_read(reader)?),
+            4 => Self::Chat(Chat::minecraft_read(reader)?),
+            5 => Self::OptionalChat(read_optional(reader)?),
+            6 => Self::Slot(Slot::minecraft_read(reader)?),
+            7 => Self::Boolean(bool::minecraft_read(reader)?),
+            8 => {
+                let (x, y, z) = <(f32, f32, f32)>::minecraft_read(reader)?;
+                Self::Rotation(x, y, z)
+            }
+            9 => Self::Position(Position::minecraft_read(reader)?),
+            10 => Self::OptionalPosition(read_optional(reader)?),
+            11 => Self::Direction(Facing::minecraft_read(reader)?),
+            12 => Self::OptionalUuid(read_optional(reader)?),
+            13 => Self::BlockState(VarInt::minecraft_read(reader)?),
+            14 => Self::Nbt(Nbt::minecraft_read(reader)?),
+            15 => Self::Particle(Particle::minecraft_read(reader)?),
+            16 => {
+                let (villager_type, profession, level) =
+                    <(VarInt, VarInt, VarInt)>::minecraft_read(reader)?;
+                Self::VillagerData(VillagerData {
+                    villager_type,
+                    profession,
+                    level,
+                })
+            }
+            17 => {
+                let value = VarInt::minecraft_read(reader)?.0;
+                Self::OptionalVarInt((value != 0).then(|| VarInt(value - 1)))
+            }
+            18 => Self::Pose(Pose::minecraft_read(reader)?),
+            type_id => Err(MetadataError::UnknownType(type_id))?,
+        })
+    }
+}
+
+/// The metadata of an entity: values by index. A packet only carries the
+/// indices that changed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntityMetadata {
+    pub entries: Vec<(u8, MetadataValue)>,
+}
+
+impl EntityMetadata {
+    pub fn get(&self, index: u8) -> Option<&MetadataValue> {
+        self.entries
+            .iter()
+            .find(|(entry_index, _)| *entry_index == index)
+            .map(|(_, value)| value)
+    }
+
+    pub fn set(&mut self, index: u8, value: MetadataValue) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|(entry_index, _)| *entry_index == index)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.entries.push((index, value)),
+        }
+    }
+
+    /// Takes on the values from an update, keeping those it does not
+    /// mention.
+    pub fn apply(&mut self, update: EntityMetadata) {
+        for (index, value) in update.entries {
+            self.set(index, value);
+        }
+    }
+
+    /// The `FLAG_*` bits, if they have been sent.
+    pub fn flags(&self) -> Option<u8> {
+        match self.get(FLAGS_INDEX)? {
+            MetadataValue::Byte(flags) => Some(*flags as u8),
+            _ => None,
+        }
+    }
+
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags().is_some_and(|flags| flags & flag != 0)
+    }
+
+    pub fn custom_name(&self) -> Option<&Chat> {
+        match self.get(CUSTOM_NAME_INDEX)? {
+            MetadataValue::OptionalChat(name) => name.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn pose(&self) -> Option<Pose> {
+        match self.get(POSE_INDEX)? {
+            MetadataValue::Pose(pose) => Some(*pose),
+            _ => None,
+        }
+    }
+}
+
+impl MinecraftIo for EntityMetadata {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        for (index, value) in &self.entries {
+            index.minecraft_write(writer)?;
+            value.minecraft_write(writer)?;
+        }
+        END_OF_METADATA.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let mut metadata = Self::default();
+        loop {
+            let index = u8::minecraft_read(reader)?;
+            if index == END_OF_METADATA {
+                return Ok(metadata);
+            }
+            let value = MetadataValue::minecraft_read(reader)?;
+            metadata.entries.push((index, value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::packets::EntityMetadataPacket;
+    use crate::network::{Direction, Packet, State};
+
+    fn round_trip(metadata: &EntityMetadata) -> EntityMetadata {
+        let mut bytes = vec![];
+        metadata.minecraft_write(&mut bytes).unwrap();
+        let mut reader = &bytes[..];
+        let read = EntityMetadata::minecraft_read(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        read
+    }
+
+    #[test]
+    fn test_read_player_metadata() {
+        // Flags: crouching; air ticks 300; no custom name; pose: crouching.
+        let bytes = [
+            0x00, 0x00, 0x02, 0x01, 0x01, 0xac, 0x02, 0x02, 0x05, 0x00, 0x06, 0x12, 0x05, 0xff,
+        ];
+        let metadata = EntityMetadata::minecraft_read(&mut &bytes[..]).unwrap();
+        assert_eq!(metadata.entries.len(), 4);
+        assert!(metadata.has_flag(FLAG_CROUCHING));
+        assert!(!metadata.has_flag(FLAG_ON_FIRE));
+        assert_eq!(metadata.get(1), Some(&MetadataValue::VarInt(VarInt(300))));
+        assert_eq!(metadata.custom_name(), None);
+        assert_eq!(metadata.pose(), Some(Pose::Crouching));
+        assert_eq!(round_trip(&metadata), metadata);
+    }
+
+    #[test]
+    fn test_round_trip_every_type() {
+        let values = vec![
+            MetadataValue::Byte(-3),
+            MetadataValue::VarInt(VarInt(-1)),
+            MetadataValue::Float(1.5),
+            MetadataValue::String("hello".to_owned()),
+            MetadataValue::Chat(Chat(r#"{"text":"hi"}"#.to_owned())),
+            MetadataValue::OptionalChat(Some(Chat(r#""Bob""#.to_owned()))),
+            MetadataValue::OptionalChat(None),
+            MetadataValue::Slot(Slot::Item {
+                id: VarInt(1),
+                count: 64,
+                nbt: None,
+            }),
+            MetadataValue::Boolean(true),
+            MetadataValue::Rotation(0.0, 90.0, -45.0),
+            MetadataValue::Position(Position { x: 1, y: -2, z: 3 }),
+            MetadataValue::OptionalPosition(Some(Position { x: 0, y: 64, z: 0 })),
+            MetadataValue::OptionalPosition(None),
+            MetadataValue::Direction(Facing::West),
+            MetadataValue::OptionalUuid(Some(Uuid::from_u128(42))),
+            MetadataValue::OptionalUuid(None),
+            MetadataValue::BlockState(VarInt(9)),
+            MetadataValue::Nbt(Nbt(nbt::Blob::new())),
+            MetadataValue::Particle(Particle {
+                id: VarInt(PARTICLE_DUST),
+                data: ParticleData::Dust {
+                    red: 1.0,
+                    green: 0.0,
+                    blue: 0.5,
+                    scale: 2.0,
+                },
+            }),
+            MetadataValue::Particle(Particle {
+                id: VarInt(PARTICLE_VIBRATION),
+                data: ParticleData::Vibration {
+                    origin: (0.5, 1.0, 2.5),
+                    destination: VibrationDestination::Entity(VarInt(7)),
+                    ticks: VarInt(20),
+                },
+            }),
+            MetadataValue::Particle(Particle {
+                id: VarInt(0),
+                data: ParticleData::None,
+            }),
+            MetadataValue::VillagerData(VillagerData {
+                villager_type: VarInt(2),
+                profession: VarInt(5),
+                level: VarInt(1),
+            }),
+            MetadataValue::OptionalVarInt(Some(VarInt(0))),
+            MetadataValue::OptionalVarInt(None),
+            MetadataValue::Pose(Pose::Unknown(42)),
+        ];
+        let metadata = EntityMetadata {
+            entries: values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| (index as u8, value))
+                .collect(),
+        };
+        assert_eq!(round_trip(&metadata), metadata);
+
+        let mut bytes = vec![];
+        MetadataValue::OptionalVarInt(Some(VarInt(0)))
+            .minecraft_write(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, [17, 1]);
+    }
+
+    #[test]
+    fn test_metadata_packet() {
+        let mut metadata = EntityMetadata::default();
+        metadata.set(FLAGS_INDEX, MetadataValue::Byte(FLAG_ON_FIRE as i8));
+        let packet = Packet::EntityMetadataPacket(EntityMetadataPacket {
+            entity_id: VarInt(12),
+            metadata: metadata.clone(),
+        });
+        let mut payload = vec![];
+        packet.write_payload(&mut payload).unwrap();
+        let Packet::EntityMetadataPacket(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected EntityMetadataPacket");
+        };
+        assert_eq!(read.entity_id, VarInt(12));
+
+        let mut update = EntityMetadata::default();
+        update.set(
+            CUSTOM_NAME_INDEX,
+            MetadataValue::OptionalChat(Some(Chat(r#""Dinnerbone""#.to_owned()))),
+        );
+        let mut tracked = read.metadata;
+        tracked.apply(update);
+        assert!(tracked.has_flag(FLAG_ON_FIRE));
+        assert_eq!(
+            tracked.custom_name(),
+            Some(&Chat(r#""Dinnerbone""#.to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        assert!(EntityMetadata::minecraft_read(&mut &[0x00, 0x63, 0x00, 0xff][..]).is_err());
+        assert!(EntityMetadata::minecraft_read(&mut &[0x00, 0x00, 0x00][..]).is_err());
+    }
+}
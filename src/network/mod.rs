@@ -1,15 +1,48 @@
 use std::io::{Read, Write};
 use uuid::Uuid;
 
+pub mod advancements;
+pub mod chat;
+pub mod chunk_data;
+pub mod commands;
+mod compression;
 pub mod connection;
+pub mod encryption;
+pub mod enums;
+pub mod lan;
+pub mod language;
+pub mod legacy_ping;
+pub mod light;
+pub mod limits;
+pub mod machine;
+pub mod metadata;
 // mod impl_packets;
 // mod packet_description;
 pub mod packets;
+pub mod player_info;
 mod read_write;
+pub mod recipes;
+pub mod session;
+pub mod status;
+pub mod tags;
+pub mod teams;
+pub mod trades;
 mod types;
 mod varint;
+pub mod worker;
 
-pub use crate::network::read_write::MinecraftIo;
-pub use packets::Packet;
+pub use crate::network::read_write::{ArrayError, MinecraftIo, NbtError};
+pub use advancements::{Advancement, AdvancementProgress};
+pub use commands::CommandNode;
+pub use connection::{Direction, State};
+pub use light::LightArray;
+pub use limits::Limits;
+pub use machine::ProtocolMachine;
+pub use metadata::EntityMetadata;
+pub use packets::{Packet, PacketRef};
+pub use player_info::PlayerInfoAction;
+pub use recipes::Recipe;
+pub use tags::RegistryTags;
+pub use trades::Trade;
 pub use types::*;
 pub use varint::{VarInt, VarLong};
@@ -1,26 +1,243 @@
+use crate::network::commands::CommandError;
+use crate::network::enums::*;
+use crate::network::limits::LimitError;
+use crate::network::metadata::{MetadataError, ParticleData};
+use crate::network::player_info::PlayerInfoError;
+use crate::network::recipes::RecipeError;
+use crate::network::varint::VarIntError;
 use crate::network::*;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+mod borrowed;
+#[cfg(test)]
+mod tests;
+
+pub use borrowed::{ChunkDataRef, PacketRef, PluginMessageRef, SliceReader};
+
+/// Largest frame the protocol allows: the length prefix is at most a
+/// three byte VarInt.
+pub const MAX_FRAME_LENGTH: usize = 2097151;
+
+#[derive(Copy, Clone, Debug)]
+pub enum FrameError {
+    InvalidLength(i32),
+    DataLengthMismatch {
+        expected: i32,
+        actual: usize,
+    },
+    UnknownPacket {
+        state: State,
+        direction: Direction,
+        id: i32,
+    },
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for FrameError {}
+
+/// What went wrong while decoding a packet body.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolErrorKind {
+    /// The payload ended in the middle of a field.
+    Truncated,
+    /// A length or VarInt was larger than the protocol or the current
+    /// [`Limits`] allow.
+    Oversize,
+    /// A type ID or other enum value this client does not know.
+    InvalidEnum,
+    /// Bytes were left over after the last field.
+    TrailingBytes(usize),
+    /// Anything else, like invalid UTF-8, JSON or NBT.
+    Invalid,
+}
+
+impl ProtocolErrorKind {
+    fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(error) = cause.downcast_ref::<std::io::Error>() {
+                if error.kind() == std::io::ErrorKind::UnexpectedEof {
+                    return Self::Truncated;
+                }
+            }
+            if let Some(error) = cause.downcast_ref::<VarIntError>() {
+                return match error {
+                    VarIntError::NotEnoughBytes => Self::Truncated,
+                    VarIntError::TooManyBytes => Self::Oversize,
+                };
+            }
+            if cause.is::<LimitError>() {
+                return Self::Oversize;
+            }
+            if let Some(RecipeError::GridTooLarge { .. }) = cause.downcast_ref() {
+                return Self::Oversize;
+            }
+            if cause.is::<PlayerInfoError>() || cause.is::<RecipeError>() {
+                return Self::InvalidEnum;
+            }
+            if let Some(MetadataError::UnknownType(_)) = cause.downcast_ref() {
+                return Self::InvalidEnum;
+            }
+            if let Some(
+                CommandError::InvalidNodeType(_)
+                | CommandError::UnknownParser(_)
+                | CommandError::InvalidStringKind(_),
+            ) = cause.downcast_ref()
+            {
+                return Self::InvalidEnum;
+            }
+        }
+        Self::Invalid
+    }
+}
+
+/// A packet body that could not be decoded, with where in it decoding
+/// stopped.
+#[derive(Debug)]
+pub struct ProtocolError {
+    pub state: State,
+    pub direction: Direction,
+    pub packet: &'static str,
+    pub id: i32,
+    /// The field being read, or `None` for trailing bytes.
+    pub field: Option<&'static str>,
+    /// Where the field or the trailing bytes start, counted from the start
+    /// of the payload including the packet ID.
+    pub offset: usize,
+    pub kind: ProtocolErrorKind,
+    source: Option<anyhow::Error>,
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {:?} packet {} (0x{:02x})",
+            self.state, self.direction, self.packet, self.id
+        )?;
+        if let Some(field) = self.field {
+            write!(f, " field {field}")?;
+        }
+        write!(f, " at byte {}: {:?}", self.offset, self.kind)?;
+        if let Some(source) = &self.source {
+            write!(f, ": {source}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|source| source.as_ref() as _)
+    }
+}
+
+/// A field that failed to decode, before the packet it is in is known.
+struct FieldError {
+    field: &'static str,
+    /// Offset of the field from the start of the packet body.
+    offset: usize,
+    source: anyhow::Error,
+}
+
+impl FieldError {
+    fn in_packet(
+        self,
+        (state, direction, id): (State, Direction, VarInt),
+        packet: &'static str,
+        body_offset: usize,
+    ) -> ProtocolError {
+        ProtocolError {
+            state,
+            direction,
+            packet,
+            id: id.0,
+            field: Some(self.field),
+            offset: body_offset + self.offset,
+            kind: ProtocolErrorKind::of(&self.source),
+            source: Some(self.source),
+        }
+    }
+}
+
+/// How a packet field is read: with its type's `MinecraftIo`, or by a
+/// function of the fields read before it and the reader, for fields whose
+/// layout depends on them.
+macro_rules! field_reader {
+    ($field_type:ty) => {
+        |_: &Self, reader: &mut &[u8]| <$field_type>::minecraft_read(reader)
+    };
+    ($field_type:ty, $read:expr) => {
+        $read
+    };
+}
+
+macro_rules! write_field {
+    ($value:expr, $writer:ident) => {
+        $value.minecraft_write($writer)?
+    };
+    ($value:expr, $writer:ident, $write:expr) => {
+        ($write)(&$value, $writer)?
+    };
+}
 
 macro_rules! define_packets {
     (
         $($(#[$packet_meta:meta])? $packet_name:ident {
-            $($(#[$field_meta:meta])? $field_name:ident : $field_type:ty $(= when($field_cond:expr))?),* $(,)*
+            $($(#[$field_meta:meta])? $field_name:ident : $field_type:ty
+                $(= when($field_cond:expr))?
+                $(= with($field_read:expr, $field_write:expr))?
+                $(= max($field_max:expr))?),* $(,)*
         }),* $(,)?
     ) => {
+        #[derive(Clone, Debug)]
         pub enum Packet {
             $($packet_name($packet_name)),*
         }
 
+        impl Packet {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Packet::$packet_name(_) => stringify!($packet_name)),*
+                }
+            }
+
+            fn write_body(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+                match self {
+                    $(Packet::$packet_name(packet) => packet.write_to(writer)),*
+                }
+            }
+        }
+
         $(#[derive(Clone, Debug, Default)]
         $(#[$packet_meta])?
         pub struct $packet_name {
             $($(#[$field_meta])? pub $field_name : $field_type),*
         }
         impl $packet_name {
-            fn read_from(reader: &mut impl Read) -> anyhow::Result<Self> {
+            fn read_from(reader: &mut &[u8]) -> Result<Self, FieldError> {
+                let start = reader.len();
                 let mut result = Self::default();
                 $(
                     if true $(&& ($field_cond as fn(&Self) -> bool)(&result))? {
-                        result.$field_name = <$field_type>::minecraft_read(reader)?;
+                        let offset = start - reader.len();
+                        let read = field_reader!($field_type $(, $field_read)?);
+                        $(let read = |packet: &Self, reader: &mut &[u8]| {
+                            Limits::current()
+                                .with_max_string_length($field_max)
+                                .scope(|| read(packet, reader))
+                        };)?
+                        result.$field_name = read(&result, reader)
+                            .map_err(|source| FieldError {
+                                field: stringify!($field_name),
+                                offset,
+                                source,
+                            })?;
                     }
                 )*
                 Ok(result)
@@ -29,7 +246,7 @@ macro_rules! define_packets {
             fn write_to(&self, writer: &mut impl Write) -> anyhow::Result<()> {
                 $(
                     if true $(&& ($field_cond as fn(&Self) -> bool)(self))? {
-                        self.$field_name.minecraft_write(writer)?;
+                        write_field!(self.$field_name, writer $(, $field_write)?);
                     }
                 )*
                 Ok(())
@@ -38,12 +255,164 @@ macro_rules! define_packets {
     }
 }
 
+macro_rules! packet_ids {
+    (
+        $($state:ident $direction:ident {
+            $($id:literal => $packet_name:ident),* $(,)?
+        })*
+    ) => {
+        impl Packet {
+            /// The connection state, direction and protocol ID this packet is
+            /// sent with.
+            pub fn id(&self) -> (State, Direction, VarInt) {
+                match self {
+                    $($(Packet::$packet_name(_) => {
+                        (State::$state, Direction::$direction, VarInt($id))
+                    })*)*
+                }
+            }
+
+            /// Decodes the body after the packet ID, which ended `body_offset`
+            /// bytes into the payload.
+            fn read_body(
+                state: State,
+                direction: Direction,
+                id: VarInt,
+                body_offset: usize,
+                reader: &mut &[u8],
+            ) -> anyhow::Result<Self> {
+                match (state, direction, id.0) {
+                    $($((State::$state, Direction::$direction, $id) => {
+                        match $packet_name::read_from(reader) {
+                            Ok(packet) => Ok(Packet::$packet_name(packet)),
+                            Err(error) => Err(error.in_packet(
+                                $packet_name::ID,
+                                stringify!($packet_name),
+                                body_offset,
+                            ))?,
+                        }
+                    })*)*
+                    (state, direction, id) => Err(FrameError::UnknownPacket {
+                        state,
+                        direction,
+                        id,
+                    })?,
+                }
+            }
+        }
+
+        $($(impl $packet_name {
+            /// The connection state, direction and protocol ID this packet
+            /// is sent with.
+            pub const ID: (State, Direction, VarInt) =
+                (State::$state, Direction::$direction, VarInt($id));
+        })*)*
+    }
+}
+
+/// Reads the contents of one length-prefixed frame, which may be at most
+/// as long as the current limits allow.
+pub fn read_frame_bytes(reader: &mut impl Read) -> anyhow::Result<Vec<u8>> {
+    let mut frame = vec![];
+    read_frame_into(reader, &mut frame)?;
+    Ok(frame)
+}
+
+/// Like `read_frame_bytes`, but replaces the contents of `frame`, so its
+/// allocation can be reused from one packet to the next.
+pub fn read_frame_into(reader: &mut impl Read, frame: &mut Vec<u8>) -> anyhow::Result<()> {
+    let length = VarInt::read(reader)?;
+    if length.0 < 0 || length.0 as usize > Limits::current().max_frame_length {
+        Err(FrameError::InvalidLength(length.0))?;
+    }
+    frame.clear();
+    frame.resize(length.0 as usize, 0);
+    reader.read_exact(frame)?;
+    Ok(())
+}
+
+/// Writes `frame` prefixed with its length.
+pub fn write_frame_bytes(writer: &mut impl Write, frame: &[u8]) -> anyhow::Result<()> {
+    if frame.len() > MAX_FRAME_LENGTH {
+        Err(FrameError::InvalidLength(frame.len() as i32))?;
+    }
+    VarInt(frame.len() as i32).write(writer)?;
+    writer.write_all(frame)?;
+    Ok(())
+}
+
+/// Fails if `rest`, what is left of `payload` after decoding the packet, is
+/// not empty.
+fn check_trailing_bytes(
+    (state, direction, id): (State, Direction, VarInt),
+    packet: &'static str,
+    payload: &[u8],
+    rest: &[u8],
+) -> anyhow::Result<()> {
+    if !rest.is_empty() {
+        Err(ProtocolError {
+            state,
+            direction,
+            packet,
+            id: id.0,
+            field: None,
+            offset: payload.len() - rest.len(),
+            kind: ProtocolErrorKind::TrailingBytes(rest.len()),
+            source: None,
+        })?;
+    }
+    Ok(())
+}
+
+impl Packet {
+    /// Reads one length-prefixed packet from `reader` and decodes it with the
+    /// ID table for `state` and `direction`.
+    pub fn read_frame(
+        state: State,
+        direction: Direction,
+        reader: &mut impl Read,
+    ) -> anyhow::Result<Self> {
+        let payload = read_frame_bytes(reader)?;
+        Self::read_payload(state, direction, &payload)
+    }
+
+    /// Decodes a packet ID followed by the packet body, i.e. a frame with the
+    /// length prefix already removed. A body that fails to decode or does not
+    /// use the whole payload is a [`ProtocolError`].
+    pub fn read_payload(
+        state: State,
+        direction: Direction,
+        payload: &[u8],
+    ) -> anyhow::Result<Self> {
+        let mut reader = payload;
+        let id = VarInt::decode(&mut reader)?;
+        let body_offset = payload.len() - reader.len();
+        let packet = Self::read_body(state, direction, id, body_offset, &mut reader)?;
+        check_trailing_bytes((state, direction, id), packet.name(), payload, reader)?;
+        Ok(packet)
+    }
+
+    /// Writes this packet as a length-prefixed frame.
+    pub fn write_frame(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let mut payload = vec![];
+        self.write_payload(&mut payload)?;
+        write_frame_bytes(writer, &payload)
+    }
+
+    /// Writes the packet ID followed by the packet body.
+    pub fn write_payload(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let (_, _, id) = self.id();
+        id.write(writer)?;
+        self.write_body(writer)
+    }
+}
+
 define_packets! {
     // clientbound handshake
     // serverbound handshake
     Handshake {
         protocol_version: VarInt,
-        server_address: String,
+        server_address: String = max(255),
         server_port: u16,
         next_state: VarInt,
     },
@@ -55,16 +424,16 @@ define_packets! {
     StatusPing { payload: i64 },
     // clientbound login
     Disconnect {
-        reason: String,
+        reason: Chat,
     },
     EncryptionRequest {
-        server_id: String,
+        server_id: String = max(20),
         public_key: LengthPrefixedByteArray<VarInt>,
         verify_token: LengthPrefixedByteArray<VarInt>,
     },
     LoginSuccess {
         uuid: Uuid,
-        usename: String,
+        usename: String = max(16),
     },
     SetCompression {
         threshold: VarInt,
@@ -76,7 +445,7 @@ define_packets! {
     },
     // serverbound login
     LoginStart {
-        username: String,
+        username: String = max(16),
     },
     EncryptionResponse {
         shared_secret: LengthPrefixedByteArray<VarInt>,
@@ -91,7 +460,7 @@ define_packets! {
     SpawnEntity {
         entity_id: VarInt,
         object_uuid: Uuid,
-        object_type: VarInt, // enum
+        object_type: VarInt, // entity type
         x: f64,
         y: f64,
         z: f64,
@@ -118,6 +487,7 @@ define_packets! {
         z: f64,
         yaw: Angle,
         pitch: Angle,
+        head_yaw: Angle,
         velocity_x: i16,
         velocity_y: i16,
         velocity_z: i16,
@@ -127,7 +497,7 @@ define_packets! {
         entity_uuid: Uuid,
         painting_id: VarInt,
         location: Position,
-        direction: i8, // enum
+        direction: HorizontalFacing,
     },
     SpawnPlayer {
         entity_id: VarInt,
@@ -141,8 +511,8 @@ define_packets! {
     SkulkVibrationSignal {
         source_position: Position,
         destination_identifier: Identifier,
-        destination_entity: Option<VarInt> = when(|s: &Self| s.destination_identifier == "entity"),
-        destination_position: Option<Position> = when(|s: &Self| s.destination_identifier == "block"),
+        destination_entity: Option<VarInt> = when(|s: &Self| s.destination_identifier == "minecraft:entity"),
+        destination_position: Option<Position> = when(|s: &Self| s.destination_identifier == "minecraft:block"),
         arrival_ticks: VarInt,
     },
     EntityAnimation {
@@ -150,12 +520,12 @@ define_packets! {
         animation: u8, // animation ID
     },
     Statistics {
-        statistics: LengthPrefixedArray<VarInt, Statistic>,
+        statistics: Vec<Statistic>,
     },
     AcknowlegePlayerDigging {
         location: Position,
         block: VarInt,
-        status: VarInt, // enum
+        status: DiggingStatus,
         successful: bool,
     },
     BlockBreakAnimation {
@@ -188,12 +558,12 @@ define_packets! {
         flags: Option<u8> = when(|s| s.action == VarInt(0) || s.action == VarInt(5)),
     },
     ServerDifficulty {
-        difficulty: u8, // enum
+        difficulty: Difficulty,
         difficulty_locked: bool,
     },
     ChatMessage {
-        chat_json: String,
-        position: u8, // enum
+        chat_json: Chat,
+        position: ChatPosition,
         sender: Uuid,
     },
     ClearTitles {
@@ -203,7 +573,7 @@ define_packets! {
         id: VarInt,
         start: VarInt,
         length: VarInt,
-        matches: LengthPrefixedArray<VarInt, (String, bool, Option<String>)>, // TODO needs attention
+        matches: Vec<(String, PrefixedOption<Chat>)>, // match, tooltip
     },
     // audited through here
     DeclareCommands {
@@ -215,7 +585,7 @@ define_packets! {
     },
     WindowItems {
         window_id: u8,
-        state_id: VarInt, // enum
+        state_id: VarInt,
         slot_data: Vec<Slot>,
         carried_item: Slot,
     },
@@ -236,35 +606,35 @@ define_packets! {
     },
     ServerPluginMessage {
         channel: Identifier,
-        data: Vec<u8>,
+        data: ByteArray,
     },
     NamedSoundEffect {
         sound_name: Identifier,
-        sound_category: VarInt, // enum
+        sound_category: SoundCategory,
         effect_position: (i32, i32, i32),
         volume: f32,
         pitch: f32,
     },
     Kick {
-        reason: String,
+        reason: Chat,
     },
     EntityStatus {
         entity_id: i32,
-        entity_status: i8, // enum
+        entity_status: i8, // depends on the entity type
     },
     Explosion {
         x: f32,
         y: f32,
         z: f32,
         strength: f32,
-        records: Vec<(u8, u8, u8)>,
+        records: Vec<(i8, i8, i8)>, // offsets of destroyed blocks
         player_motion: (f32, f32, f32),
     },
     UnloacChunk {
         chunk: (i32, i32),
     },
     ChangeGameState {
-        reason: u8,
+        reason: GameStateChange,
         value: f32,
     },
     OpenHorseWindow {
@@ -289,7 +659,6 @@ define_packets! {
         chunk_x: i32,
         chunk_z: i32,
         heightmaps: Nbt,
-        size: VarInt,
         data: Vec<u8>,
         block_entities: Vec<(i8, i16, VarInt, Nbt)>,
         trust_edges: bool,
@@ -297,8 +666,8 @@ define_packets! {
         block_light_mask: BitSet,
         empty_sky_light_mask: BitSet,
         empty_block_light_mask: BitSet,
-        sky_light_array: Vec<()>,
-        block_light_array: Vec<()>,
+        sky_light_array: Vec<LightArray>,
+        block_light_array: Vec<LightArray>,
     },
     Effect {
         effect_id: i32,
@@ -317,7 +686,10 @@ define_packets! {
         offset_z: f32,
         particle_data: f32,
         particle_count: i32,
-        data: (),
+        data: ParticleData = with(
+            |s: &Self, reader: &mut &[u8]| ParticleData::read(s.particle_id, reader),
+            ParticleData::write
+        ),
     },
     UpdateLight {
         chunk_x: VarInt,
@@ -327,15 +699,14 @@ define_packets! {
         block_light_mask: BitSet,
         empty_sky_light_mask: BitSet,
         empty_block_light_mask: BitSet,
-        sky_light_array: Vec<()>,
-        block_light_array: Vec<()>,
+        sky_light_array: Vec<LightArray>,
+        block_light_array: Vec<LightArray>,
     },
     JoinGame {
         entity_id: i32,
         is_hardcore: bool,
-        gamemode: u8,
-        previous_gamemode: i8,
-        world_count: VarInt,
+        gamemode: AsByte<GameMode>,
+        previous_gamemode: PreviousGameMode,
         dimension_names: Vec<Identifier>,
         dimension_codec: Nbt,
         dimension: Nbt,
@@ -354,17 +725,20 @@ define_packets! {
         scale: i8,
         locked: bool,
         tracking_position: bool,
-        icon_count: VarInt,
-        icons: Vec<(VarInt, i8, i8, i8, Option<String>)>,
-        columns: Option<u8>,
-        rows: Option<u8>,
-        x: Option<i8>,
-        z: Option<i8>,
-        length: Option<VarInt>,
-        data: Option<Vec<u8>>,
+        icons: Option<Vec<(VarInt, i8, i8, i8, PrefixedOption<Chat>)>> = when(|s| s.tracking_position),
+        columns: u8,
+        rows: Option<u8> = when(|s| s.columns > 0),
+        x: Option<i8> = when(|s| s.columns > 0),
+        z: Option<i8> = when(|s| s.columns > 0),
+        data: Option<Vec<u8>> = when(|s| s.columns > 0),
     },
     TradeList {
-        // TODO
+        window_id: VarInt,
+        trades: LengthPrefixedArray<u8, Trade>,
+        villager_level: VarInt,
+        experience: VarInt,
+        is_regular_villager: bool,
+        can_restock: bool,
     },
     EntityPosition {
         entity_id: VarInt,
@@ -383,6 +757,7 @@ define_packets! {
         on_ground: bool,
     },
     EntityRotation {
+        entity_id: VarInt,
         yaw: Angle,
         pitch: Angle,
         on_ground: bool,
@@ -395,7 +770,7 @@ define_packets! {
         pitch: f32,
     },
     OpenBook {
-        hand: VarInt, // enum
+        hand: Hand,
     },
     OpenWindow {
         window_id: VarInt,
@@ -421,21 +796,23 @@ define_packets! {
         duration: VarInt,
         entity_id: i32,
     },
-    EnterCombatEvent {
+    EnterCombatEvent {},
+    DeathCombatEvent {
         player_id: VarInt,
-        entity_id: VarInt,
-        message: String,
+        entity_id: i32,
+        message: Chat,
     },
     PlayerInfo {
-        // TODO
+        action: PlayerInfoAction,
     },
     Face {
-        feet_eyes: VarInt, // enum
+        feet_eyes: LookAnchor,
         target_x: f64,
         target_y: f64,
         target_z: f64,
-        entity_id: Option<VarInt>,
-        entity_feet_eyes: Option<VarInt>, // enum
+        is_entity: bool,
+        entity_id: Option<VarInt> = when(|s| s.is_entity),
+        entity_feet_eyes: Option<LookAnchor> = when(|s| s.is_entity),
     },
     PlayerPositionAndLook {
         x: f64,
@@ -457,8 +834,8 @@ define_packets! {
         blast_furnace_recipe_book_filter_active: bool,
         smoker_recipe_book_open: bool,
         smoker_recipe_book_filter_active: bool,
-        recipe_ids: Vec<VarInt>,
-        recipe_ids_init: Vec<VarInt>, // only if action == 0
+        recipe_ids: Vec<Identifier>,
+        recipe_ids_init: Option<Vec<Identifier>> = when(|s| s.action == VarInt(0)),
     },
     DestroyEntities {
         entity_ids: Vec<VarInt>,
@@ -469,17 +846,17 @@ define_packets! {
     },
     ResourcePackSend {
         url: String,
-        hash: String,
+        hash: String = max(40),
         forced: bool,
         has_prompt_message: bool,
-        prompt_message: Option<String>,
+        prompt_message: Option<Chat> = when(|s| s.has_prompt_message),
     },
     Respawn {
         dimension: Nbt,
         dimension_name: Identifier,
         hashed_seed: i64,
-        gamemode: u8,
-        previous_gamemode: u8,
+        gamemode: AsByte<GameMode>,
+        previous_gamemode: PreviousGameMode,
         is_debug: bool,
         is_flag: bool,
         copy_metadata: bool,
@@ -491,15 +868,14 @@ define_packets! {
     MultiBlockChange {
         chunk_section_position: i64,
         trust_light_edges: bool, // always inverse of previous UpdateLight packet
-        blocks_array_size: VarInt,
         blocks: Vec<VarLong>,
     },
     SelectAdvancement {
         has_id: bool,
-        identifier: Option<Identifier>,
+        identifier: Option<Identifier> = when(|s| s.has_id),
     },
     ActionBar {
-        action_bar_text: String,
+        action_bar_text: Chat,
     },
     WorldBorderCenter {
         x: f64,
@@ -538,7 +914,7 @@ define_packets! {
     },
     DisplayScoreboard {
         position: i8,
-        score_name: String,
+        score_name: String = max(16),
     },
     EntityMetadataPacket {
         entity_id: VarInt,
@@ -556,7 +932,7 @@ define_packets! {
     },
     EntityEquipment {
         entity_id: VarInt,
-        equipment: Vec<(i8, Slot)>,
+        equipment: Equipment,
     },
     SetExperience {
         experience_bar: f32,
@@ -569,37 +945,45 @@ define_packets! {
         food_saturation: f32,
     },
     ScoreboardObjective {
-        objective_name: String,
+        objective_name: String = max(16),
         mode: i8,
-        objective_value: Option<String>, // rest only if mode is 0 or 2
-        objective_type: Option<VarInt>,
+        objective_value: Option<Chat> = when(|s| s.mode == 0 || s.mode == 2),
+        objective_type: Option<ObjectiveType> = when(|s| s.mode == 0 || s.mode == 2),
     },
     SetPassengers {
         entity_id: VarInt,
-        passenger_count: VarInt,
         passengers: Vec<VarInt>,
     },
     Teams {
-        // TODO
+        team_name: String = max(16),
+        mode: i8, // 0 create, 1 remove, 2 update, 3 add entities, 4 remove entities
+        display_name: Option<Chat> = when(|s| s.mode == 0 || s.mode == 2),
+        friendly_flags: Option<i8> = when(|s| s.mode == 0 || s.mode == 2),
+        name_tag_visibility: Option<String> = when(|s| s.mode == 0 || s.mode == 2) = max(40),
+        collision_rule: Option<String> = when(|s| s.mode == 0 || s.mode == 2) = max(40),
+        team_color: Option<TeamColor> = when(|s| s.mode == 0 || s.mode == 2),
+        prefix: Option<Chat> = when(|s| s.mode == 0 || s.mode == 2),
+        suffix: Option<Chat> = when(|s| s.mode == 0 || s.mode == 2),
+        entities: Option<Vec<String>> = when(|s| s.mode == 0 || s.mode == 3 || s.mode == 4) = max(40),
     },
     UpdateScore {
-        entity_name: String,
+        entity_name: String = max(40),
         action: VarInt,
-        objective_name: String,
-        value: Option<VarInt>, // if action != 1
+        objective_name: String = max(16),
+        value: Option<VarInt> = when(|s| s.action != VarInt(1)),
     },
     UpdateSimulationDistance {
         simulation_distance: VarInt,
     },
     SetTitleSubTitle {
-        subtitle_text: String,
+        subtitle_text: Chat,
     },
     TimeUpdate {
         world_age: i64,
         time_of_day: i64,
     },
     SetTitleText {
-        title_text: String,
+        title_text: Chat,
     },
     SetTitleTimes {
         fade_in: i32,
@@ -608,14 +992,14 @@ define_packets! {
     },
     EntitySoundEffect {
         sound_id: VarInt,
-        sound_category: VarInt,
+        sound_category: SoundCategory,
         entity_id: VarInt,
         volume: f32,
         pitch: f32,
     },
     SoundEffect {
         sound_id: VarInt,
-        sound_category: VarInt,
+        sound_category: SoundCategory,
         effect_position_x: i32,
         effect_position_y: i32,
         effect_position_z: i32,
@@ -624,12 +1008,12 @@ define_packets! {
     },
     StopSound {
         flags: u8,
-        source: Option<VarInt>,    // only if flags is 1 or 3 (& 0x01)
-        sound: Option<Identifier>, // only if flags is 2 or 3 (& 0x02)
+        source: Option<SoundCategory> = when(|s| s.flags & 0x01 != 0),
+        sound: Option<Identifier> = when(|s| s.flags & 0x02 != 0),
     },
     PlayListHeaderAndFooter {
-        header: String,
-        footer: String,
+        header: Chat,
+        footer: Chat,
     },
     NbtQueryResponse {
         transaction_id: VarInt,
@@ -650,27 +1034,27 @@ define_packets! {
         on_ground: bool,
     },
     Advancements {
-        // TODO
+        reset: bool,
+        advancements: Vec<(Identifier, Advancement)>,
+        removed: Vec<Identifier>,
+        progress: Vec<(Identifier, AdvancementProgress)>,
     },
     EntityProperties {
         entity_id: VarInt,
-        property_count: VarInt,
         properties: Vec<EntityProperty>,
     },
     EntityEffect {
         entity_id: VarInt,
-        effect_id: VarInt,
-        amplifier: i16,
+        effect_id: i8,
+        amplifier: i8,
         duration: VarInt,
         flags: i8,
     },
     DeclareRecipes {
-        recipe_count: VarInt,
         recipes: Vec<Recipe>,
     },
     Tags {
-        tag_count: VarInt,
-        tags: Vec<Tag>,
+        tags: Vec<RegistryTags>,
     },
     // serverbound play
     TeleportConfirm {
@@ -681,27 +1065,27 @@ define_packets! {
         location: Position,
     },
     SetDifficulty {
-        new_difficulty: i8,
+        new_difficulty: Difficulty,
     },
     SendChatMessage {
-        message: String,
+        message: String = max(256),
     },
     ClientStatus {
-        action_id: VarInt,
+        action_id: ClientStatusAction,
     },
     ClientSettings {
-        locale: String,
+        locale: String = max(16),
         view_distance: i8,
-        chat_mode: VarInt,
+        chat_mode: ChatMode,
         chat_colors: bool,
         displayed_skin_parts: u8,
-        main_hand: VarInt,
+        main_hand: MainHand,
         enable_text_filtering: bool,
         allow_server_listings: bool,
     },
     TabCompleteRequest {
         transaction_id: VarInt,
-        text: String,
+        text: String = max(32500),
     },
     ClickWindowButton {
         window_id: i8,
@@ -721,14 +1105,13 @@ define_packets! {
     },
     ClientPluginMessage {
         channel: Identifier,
-        data: Vec<u8>,
+        data: ByteArray,
     },
     EditBook {
         slot: VarInt,
-        count: VarInt,
-        entries: Vec<String>,
+        entries: Vec<String> = max(8192),
         has_title: bool,
-        title: Option<String>,
+        title: Option<String> = when(|s| s.has_title) = max(128),
     },
     QueryEntityNbt {
         transaction_id: VarInt,
@@ -736,9 +1119,9 @@ define_packets! {
     },
     InteractEntity {
         entity_id: VarInt,
-        entity_type: VarInt,
-        position: Option<(f32, f32, f32)>,
-        hand: Option<VarInt>,
+        interaction_type: InteractionType,
+        position: Option<(f32, f32, f32)> = when(|s| s.interaction_type == InteractionType::InteractAt),
+        hand: Option<Hand> = when(|s| s.interaction_type != InteractionType::Attack),
         sneaking: bool,
     },
     GenerateStructure {
@@ -797,13 +1180,13 @@ define_packets! {
         flags: u8,
     },
     PlayerDigging {
-        status: VarInt, // enum
+        status: DiggingStatus,
         location: Position,
-        face: u8, // enum
+        face: AsByte<Facing>,
     },
     EntityAction {
         entity_id: VarInt,
-        action_id: VarInt, // enum
+        action_id: PlayerAction,
         jump_boost: VarInt,
     },
     SteerVehicle {
@@ -815,7 +1198,7 @@ define_packets! {
         id: i32,
     },
     SetRecipeBookState {
-        book_id: VarInt, // enum
+        book_id: RecipeBookType,
         book_open: bool,
         filter_active: bool,
     },
@@ -826,12 +1209,11 @@ define_packets! {
         item_name: String,
     },
     ResourcePackStatus {
-        result: VarInt, // enum
+        result: ResourcePackResult,
     },
     AdvancementTab {
-        action: VarInt,
-        // enum
-        tab_id: Option<Identifier>,
+        action: AdvancementTabAction,
+        tab_id: Option<Identifier> = when(|s| s.action == AdvancementTabAction::OpenedTab),
     },
     SelectTrade {
         selected_slot: VarInt,
@@ -846,7 +1228,7 @@ define_packets! {
     UpdateCommandBlock {
         location: Position,
         command: String,
-        mode: VarInt, // enum
+        mode: CommandBlockMode,
         flags: u8,
     },
     UpdateCommandBlockMinecart {
@@ -868,8 +1250,8 @@ define_packets! {
     },
     UpdateStructureBlock {
         location: Position,
-        action: VarInt, // enum
-        mode: VarInt,   // enum
+        action: StructureBlockAction,
+        mode: StructureBlockMode,
         name: String,
         offset_x: i8,
         offset_y: i8,
@@ -877,8 +1259,8 @@ define_packets! {
         size_x: i8,
         size_y: i8,
         size_z: i8,
-        mirror: VarInt,   // enum
-        rotation: VarInt, // enum
+        mirror: Mirror,
+        rotation: Rotation,
         metadata: String,
         integrity: f32,
         seed: VarLong,
@@ -886,25 +1268,204 @@ define_packets! {
     },
     UpdateSign {
         location: Position,
-        line1: String,
-        line2: String,
-        line3: String,
-        line4: String,
+        lines: [String; 4] = max(384),
     },
     Animation {
-        hand: VarInt, // enum
+        hand: Hand,
     },
     Spectate {
         target_player: Uuid,
     },
     PlayerBlockPlacement {
-        hand: VarInt, // enum
+        hand: Hand,
         location: Position,
-        face: VarInt, // enum
+        face: Facing,
         cursor_position: (f32, f32, f32),
         inside_block: bool,
     },
     UseItem {
-        hand: VarInt, // enum
+        hand: Hand,
     },
 }
+
+packet_ids! {
+    Handshake ServerBound {
+        0x00 => Handshake,
+    }
+    Status ClientBound {
+        0x00 => StatusResponse,
+        0x01 => StatusPong,
+    }
+    Status ServerBound {
+        0x00 => StatusRequest,
+        0x01 => StatusPing,
+    }
+    Login ClientBound {
+        0x00 => Disconnect,
+        0x01 => EncryptionRequest,
+        0x02 => LoginSuccess,
+        0x03 => SetCompression,
+        0x04 => LoginPluginRequest,
+    }
+    Login ServerBound {
+        0x00 => LoginStart,
+        0x01 => EncryptionResponse,
+        0x02 => LoginPluginResponse,
+    }
+    Play ClientBound {
+        0x00 => SpawnEntity,
+        0x01 => SpawnExperienceOrb,
+        0x02 => SpawnLivingEntity,
+        0x03 => SpawnPainting,
+        0x04 => SpawnPlayer,
+        0x05 => SkulkVibrationSignal,
+        0x06 => EntityAnimation,
+        0x07 => Statistics,
+        0x08 => AcknowlegePlayerDigging,
+        0x09 => BlockBreakAnimation,
+        0x0A => BlockEntityData,
+        0x0B => BlockAction,
+        0x0C => BlockChange,
+        0x0D => BossBar,
+        0x0E => ServerDifficulty,
+        0x0F => ChatMessage,
+        0x10 => ClearTitles,
+        0x11 => TabCompleteResponse,
+        0x12 => DeclareCommands,
+        0x13 => CloseWindow,
+        0x14 => WindowItems,
+        0x15 => WindowProperty,
+        0x16 => SetSlot,
+        0x17 => SetCooldown,
+        0x18 => ServerPluginMessage,
+        0x19 => NamedSoundEffect,
+        0x1A => Kick,
+        0x1B => EntityStatus,
+        0x1C => Explosion,
+        0x1D => UnloacChunk,
+        0x1E => ChangeGameState,
+        0x1F => OpenHorseWindow,
+        0x20 => InitializeWorldBorder,
+        0x21 => KeepAliveRequest,
+        0x22 => ChunkDataAndUpdateLight,
+        0x23 => Effect,
+        0x24 => Particle,
+        0x25 => UpdateLight,
+        0x26 => JoinGame,
+        0x27 => MapData,
+        0x28 => TradeList,
+        0x29 => EntityPosition,
+        0x2A => EntityPositionAndRotation,
+        0x2B => EntityRotation,
+        0x2C => VehicleMove,
+        0x2D => OpenBook,
+        0x2E => OpenWindow,
+        0x2F => OpenSignEditor,
+        0x30 => PlayPing,
+        0x31 => CraftRecipeResponse,
+        0x32 => SetPlayerAbilities,
+        0x33 => EndCombatEvent,
+        0x34 => EnterCombatEvent,
+        0x35 => DeathCombatEvent,
+        0x36 => PlayerInfo,
+        0x37 => Face,
+        0x38 => PlayerPositionAndLook,
+        0x39 => UnlockRecipes,
+        0x3A => DestroyEntities,
+        0x3B => RemoveEntityEffect,
+        0x3C => ResourcePackSend,
+        0x3D => Respawn,
+        0x3E => EntityHeadLook,
+        0x3F => MultiBlockChange,
+        0x40 => SelectAdvancement,
+        0x41 => ActionBar,
+        0x42 => WorldBorderCenter,
+        0x43 => WorldBorderLerpSize,
+        0x44 => WorldBorderSize,
+        0x45 => WorldBorderWarningDelay,
+        0x46 => WorldBorderWarningReach,
+        0x47 => Camera,
+        0x48 => SetHeldItem,
+        0x49 => UpdateViewPosition,
+        0x4A => UpdateViewDistance,
+        0x4B => SpawnPosition,
+        0x4C => DisplayScoreboard,
+        0x4D => EntityMetadataPacket,
+        0x4E => AttachEntity,
+        0x4F => EntityVelocity,
+        0x50 => EntityEquipment,
+        0x51 => SetExperience,
+        0x52 => UpdateHealth,
+        0x53 => ScoreboardObjective,
+        0x54 => SetPassengers,
+        0x55 => Teams,
+        0x56 => UpdateScore,
+        0x57 => UpdateSimulationDistance,
+        0x58 => SetTitleSubTitle,
+        0x59 => TimeUpdate,
+        0x5A => SetTitleText,
+        0x5B => SetTitleTimes,
+        0x5C => EntitySoundEffect,
+        0x5D => SoundEffect,
+        0x5E => StopSound,
+        0x5F => PlayListHeaderAndFooter,
+        0x60 => NbtQueryResponse,
+        0x61 => CollectItem,
+        0x62 => EntityTeleport,
+        0x63 => Advancements,
+        0x64 => EntityProperties,
+        0x65 => EntityEffect,
+        0x66 => DeclareRecipes,
+        0x67 => Tags,
+    }
+    Play ServerBound {
+        0x00 => TeleportConfirm,
+        0x01 => QueryBlockNbt,
+        0x02 => SetDifficulty,
+        0x03 => SendChatMessage,
+        0x04 => ClientStatus,
+        0x05 => ClientSettings,
+        0x06 => TabCompleteRequest,
+        0x07 => ClickWindowButton,
+        0x08 => ClickedWindow,
+        0x09 => ClosedWindow,
+        0x0A => ClientPluginMessage,
+        0x0B => EditBook,
+        0x0C => QueryEntityNbt,
+        0x0D => InteractEntity,
+        0x0E => GenerateStructure,
+        0x0F => KeepAliveResponse,
+        0x10 => LockDifficulty,
+        0x11 => PlayerPosition,
+        0x12 => PlayerPositionAndRotation,
+        0x13 => PlayerRotation,
+        0x14 => PlayerMovement,
+        0x15 => VehicleMoved,
+        0x16 => SteerBoat,
+        0x17 => PickItem,
+        0x18 => CraftRecipieRequest,
+        0x19 => PlayerAbilities,
+        0x1A => PlayerDigging,
+        0x1B => EntityAction,
+        0x1C => SteerVehicle,
+        0x1D => PlayPong,
+        0x1E => SetRecipeBookState,
+        0x1F => SetDelayedRecipe,
+        0x20 => NameItem,
+        0x21 => ResourcePackStatus,
+        0x22 => AdvancementTab,
+        0x23 => SelectTrade,
+        0x24 => SetBeaconEffect,
+        0x25 => SelectItem,
+        0x26 => UpdateCommandBlock,
+        0x27 => UpdateCommandBlockMinecart,
+        0x28 => CreativeInventoryAction,
+        0x29 => UpdateJigsawBlock,
+        0x2A => UpdateStructureBlock,
+        0x2B => UpdateSign,
+        0x2C => Animation,
+        0x2D => Spectate,
+        0x2E => PlayerBlockPlacement,
+        0x2F => UseItem,
+    }
+}
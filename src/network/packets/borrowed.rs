@@ -0,0 +1,361 @@
+use super::{
+    check_trailing_bytes, ChunkDataAndUpdateLight, ClientPluginMessage, FieldError, Packet,
+    ServerPluginMessage,
+};
+use crate::network::chunk_data::{read_chunk_sections, ChunkSection};
+use crate::network::light::ChunkLight;
+use crate::network::limits::Limits;
+use crate::network::read_write::{check_array_length, check_string, check_string_bytes};
+use crate::network::{
+    BitSet, ByteArray, Direction, Identifier, Index, LightArray, MinecraftIo, Nbt, State, VarInt,
+    VarLong,
+};
+use anyhow::Result;
+
+/// A cursor over a payload already in memory. Strings and byte arrays are
+/// borrowed from it instead of copied, and everything else is read with its
+/// `MinecraftIo` impl.
+#[derive(Copy, Clone, Debug)]
+pub struct SliceReader<'a> {
+    bytes: &'a [u8],
+    length: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            length: bytes.len(),
+        }
+    }
+
+    /// How many bytes have been read.
+    pub fn position(&self) -> usize {
+        self.length - self.bytes.len()
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        if length > self.bytes.len() {
+            Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Everything left, like a `ByteArray` field.
+    pub fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.bytes)
+    }
+
+    pub fn var_int(&mut self) -> Result<VarInt> {
+        VarInt::decode(&mut self.bytes)
+    }
+
+    pub fn var_long(&mut self) -> Result<VarLong> {
+        VarLong::decode(&mut self.bytes)
+    }
+
+    /// A string, held to the current limits like an owned one.
+    pub fn str(&mut self) -> Result<&'a str> {
+        let max = Limits::current().max_string_length;
+        let length = self.var_int()?.into_index()?;
+        check_string_bytes(length, max)?;
+        let string = std::str::from_utf8(self.take(length)?)?;
+        check_string(string, max)?;
+        Ok(string)
+    }
+
+    /// A byte array prefixed with its length as a VarInt.
+    pub fn byte_array(&mut self) -> Result<&'a [u8]> {
+        let length = check_array_length(self.var_int()?.into_index()?)?;
+        self.take(length)
+    }
+
+    pub fn read<T: MinecraftIo>(&mut self) -> Result<T> {
+        T::minecraft_read(&mut self.bytes)
+    }
+
+    /// Reads the field `name` with `read`, noting where it started if it
+    /// fails.
+    fn field<T>(
+        &mut self,
+        name: &'static str,
+        read: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T, FieldError> {
+        let offset = self.position();
+        read(self).map_err(|source| FieldError {
+            field: name,
+            offset,
+            source,
+        })
+    }
+}
+
+/// `ChunkDataAndUpdateLight` with the section data, usually most of the
+/// packet, borrowed from the payload.
+#[derive(Clone, Debug, Default)]
+pub struct ChunkDataRef<'a> {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub heightmaps: Nbt,
+    pub data: &'a [u8],
+    pub block_entities: Vec<(i8, i16, VarInt, Nbt)>,
+    pub trust_edges: bool,
+    pub sky_light_mask: BitSet,
+    pub block_light_mask: BitSet,
+    pub empty_sky_light_mask: BitSet,
+    pub empty_block_light_mask: BitSet,
+    pub sky_light_array: Vec<LightArray>,
+    pub block_light_array: Vec<LightArray>,
+}
+
+impl<'a> ChunkDataRef<'a> {
+    fn read_from(reader: &mut SliceReader<'a>) -> Result<Self, FieldError> {
+        Ok(Self {
+            chunk_x: reader.field("chunk_x", SliceReader::read)?,
+            chunk_z: reader.field("chunk_z", SliceReader::read)?,
+            heightmaps: reader.field("heightmaps", SliceReader::read)?,
+            data: reader.field("data", SliceReader::byte_array)?,
+            block_entities: reader.field("block_entities", SliceReader::read)?,
+            trust_edges: reader.field("trust_edges", SliceReader::read)?,
+            sky_light_mask: reader.field("sky_light_mask", SliceReader::read)?,
+            block_light_mask: reader.field("block_light_mask", SliceReader::read)?,
+            empty_sky_light_mask: reader.field("empty_sky_light_mask", SliceReader::read)?,
+            empty_block_light_mask: reader.field("empty_block_light_mask", SliceReader::read)?,
+            sky_light_array: reader.field("sky_light_array", SliceReader::read)?,
+            block_light_array: reader.field("block_light_array", SliceReader::read)?,
+        })
+    }
+
+    pub fn sections(&self) -> Result<Vec<ChunkSection>> {
+        read_chunk_sections(self.data)
+    }
+
+    pub fn light(&self) -> Result<ChunkLight> {
+        ChunkLight::decode(
+            &self.sky_light_mask,
+            &self.empty_sky_light_mask,
+            &self.sky_light_array,
+            &self.block_light_mask,
+            &self.empty_block_light_mask,
+            &self.block_light_array,
+        )
+    }
+
+    pub fn into_owned(self) -> ChunkDataAndUpdateLight {
+        ChunkDataAndUpdateLight {
+            chunk_x: self.chunk_x,
+            chunk_z: self.chunk_z,
+            heightmaps: self.heightmaps,
+            data: self.data.to_vec(),
+            block_entities: self.block_entities,
+            trust_edges: self.trust_edges,
+            sky_light_mask: self.sky_light_mask,
+            block_light_mask: self.block_light_mask,
+            empty_sky_light_mask: self.empty_sky_light_mask,
+            empty_block_light_mask: self.empty_block_light_mask,
+            sky_light_array: self.sky_light_array,
+            block_light_array: self.block_light_array,
+        }
+    }
+}
+
+/// A plugin message in either direction, with the channel and data borrowed
+/// from the payload.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PluginMessageRef<'a> {
+    pub channel: &'a str,
+    pub data: &'a [u8],
+}
+
+impl<'a> PluginMessageRef<'a> {
+    fn read_from(reader: &mut SliceReader<'a>) -> Result<Self, FieldError> {
+        Ok(Self {
+            channel: reader.field("channel", SliceReader::str)?,
+            data: reader.field("data", |reader| Ok(reader.rest()))?,
+        })
+    }
+
+    fn channel(&self) -> Identifier {
+        Identifier(self.channel.to_owned())
+    }
+
+    fn data(&self) -> ByteArray {
+        ByteArray(self.data.to_vec())
+    }
+}
+
+/// A packet decoded from a payload in memory. The packets that carry the
+/// most data borrow it from the payload; all others are decoded as a
+/// [`Packet`].
+#[derive(Clone, Debug)]
+pub enum PacketRef<'a> {
+    ChunkDataAndUpdateLight(ChunkDataRef<'a>),
+    ServerPluginMessage(PluginMessageRef<'a>),
+    ClientPluginMessage(PluginMessageRef<'a>),
+    Packet(Packet),
+}
+
+impl<'a> PacketRef<'a> {
+    /// Whether the packet with this ID is decoded borrowing from the
+    /// payload.
+    pub fn borrows(state: State, direction: Direction, id: VarInt) -> bool {
+        [
+            ChunkDataAndUpdateLight::ID,
+            ServerPluginMessage::ID,
+            ClientPluginMessage::ID,
+        ]
+        .contains(&(state, direction, id))
+    }
+
+    /// Decodes a packet ID followed by the packet body, like
+    /// `Packet::read_payload`.
+    pub fn read_payload(state: State, direction: Direction, payload: &'a [u8]) -> Result<Self> {
+        let mut reader = SliceReader::new(payload);
+        let id = reader.var_int()?;
+        if !Self::borrows(state, direction, id) {
+            return Ok(Self::Packet(Packet::read_payload(
+                state, direction, payload,
+            )?));
+        }
+
+        let body_offset = reader.position();
+        let mut body = SliceReader::new(reader.remaining());
+        let (packet, name) = if (state, direction, id) == ChunkDataAndUpdateLight::ID {
+            let packet = ChunkDataRef::read_from(&mut body);
+            (
+                packet.map(Self::ChunkDataAndUpdateLight),
+                "ChunkDataAndUpdateLight",
+            )
+        } else if (state, direction, id) == ServerPluginMessage::ID {
+            let packet = PluginMessageRef::read_from(&mut body);
+            (packet.map(Self::ServerPluginMessage), "ServerPluginMessage")
+        } else {
+            let packet = PluginMessageRef::read_from(&mut body);
+            (packet.map(Self::ClientPluginMessage), "ClientPluginMessage")
+        };
+        let packet =
+            packet.map_err(|error| error.in_packet((state, direction, id), name, body_offset))?;
+        check_trailing_bytes((state, direction, id), name, payload, body.remaining())?;
+        Ok(packet)
+    }
+
+    /// Copies the borrowed data out of the payload.
+    pub fn into_owned(self) -> Packet {
+        match self {
+            Self::ChunkDataAndUpdateLight(packet) => {
+                Packet::ChunkDataAndUpdateLight(packet.into_owned())
+            }
+            Self::ServerPluginMessage(message) => {
+                Packet::ServerPluginMessage(ServerPluginMessage {
+                    channel: message.channel(),
+                    data: message.data(),
+                })
+            }
+            Self::ClientPluginMessage(message) => {
+                Packet::ClientPluginMessage(ClientPluginMessage {
+                    channel: message.channel(),
+                    data: message.data(),
+                })
+            }
+            Self::Packet(packet) => packet,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::packets::{KeepAliveRequest, ProtocolError};
+
+    fn write(packet: Packet) -> Vec<u8> {
+        let mut payload = vec![];
+        packet.write_payload(&mut payload).unwrap();
+        payload
+    }
+
+    #[test]
+    fn test_borrowed_chunk_data() {
+        let chunk = ChunkDataAndUpdateLight {
+            chunk_x: -3,
+            chunk_z: 7,
+            data: vec![0x5a; 4000],
+            sky_light_mask: BitSet(vec![0b10]),
+            sky_light_array: vec![LightArray::default()],
+            ..ChunkDataAndUpdateLight::default()
+        };
+        let payload = write(Packet::ChunkDataAndUpdateLight(chunk.clone()));
+        let PacketRef::ChunkDataAndUpdateLight(read) =
+            PacketRef::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected ChunkDataAndUpdateLight");
+        };
+        assert!(payload.as_ptr_range().contains(&read.data.as_ptr()));
+        assert_eq!(read.data, &chunk.data[..]);
+        assert_eq!((read.chunk_x, read.chunk_z), (-3, 7));
+        assert_eq!(
+            read.light().unwrap().sky_light(1),
+            Some(&LightArray::default())
+        );
+
+        let Packet::ChunkDataAndUpdateLight(owned) =
+            PacketRef::ChunkDataAndUpdateLight(read).into_owned()
+        else {
+            panic!("expected ChunkDataAndUpdateLight");
+        };
+        assert_eq!(owned.data, chunk.data);
+        assert_eq!(owned.sky_light_array, chunk.sky_light_array);
+    }
+
+    #[test]
+    fn test_borrowed_plugin_message() {
+        let payload = write(Packet::ServerPluginMessage(ServerPluginMessage {
+            channel: Identifier("minecraft:brand".to_owned()),
+            data: ByteArray(b"\x07vanilla".to_vec()),
+        }));
+        let read = PacketRef::read_payload(State::Play, Direction::ClientBound, &payload).unwrap();
+        let PacketRef::ServerPluginMessage(message) = read.clone() else {
+            panic!("expected ServerPluginMessage");
+        };
+        assert_eq!(message.channel, "minecraft:brand");
+        assert_eq!(message.data, b"\x07vanilla");
+        assert!(matches!(
+            read.into_owned(),
+            Packet::ServerPluginMessage(owned) if owned.channel == "minecraft:brand"
+        ));
+
+        let payload = write(Packet::KeepAliveRequest(KeepAliveRequest {
+            keep_alive_id: 5,
+        }));
+        assert!(matches!(
+            PacketRef::read_payload(State::Play, Direction::ClientBound, &payload).unwrap(),
+            PacketRef::Packet(Packet::KeepAliveRequest(KeepAliveRequest {
+                keep_alive_id: 5
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_borrowed_errors() {
+        let mut payload = write(Packet::ChunkDataAndUpdateLight(ChunkDataAndUpdateLight {
+            data: vec![1; 100],
+            ..ChunkDataAndUpdateLight::default()
+        }));
+        payload.truncate(50);
+        let error = PacketRef::read_payload(State::Play, Direction::ClientBound, &payload)
+            .unwrap_err()
+            .downcast::<ProtocolError>()
+            .unwrap();
+        assert_eq!(error.packet, "ChunkDataAndUpdateLight");
+        assert_eq!(error.field, Some("data"));
+    }
+}
@@ -0,0 +1,552 @@
+    use super::*;
+
+    #[test]
+    fn test_write_handshake_frame() {
+        let mut vec = vec![];
+        let packet = Packet::Handshake(Handshake {
+            protocol_version: VarInt(758),
+            server_address: "localhost".to_owned(),
+            server_port: 25565,
+            next_state: VarInt(1),
+        });
+        packet.write_frame(&mut vec).unwrap();
+        assert_eq!(vec, b"\x10\x00\xf6\x05\x09localhost\x63\xdd\x01");
+    }
+
+    #[test]
+    fn test_read_frame_dispatch() {
+        let bytes: &[u8] = &[9, 1, 0, 0, 0, 0, 0, 0, 0, 42];
+        let packet =
+            Packet::read_frame(State::Status, Direction::ClientBound, &mut &bytes[..]).unwrap();
+        assert!(matches!(
+            packet,
+            Packet::StatusPong(StatusPong { payload: 42 })
+        ));
+
+        let packet =
+            Packet::read_frame(State::Status, Direction::ServerBound, &mut &bytes[..]).unwrap();
+        assert!(matches!(
+            packet,
+            Packet::StatusPing(StatusPing { payload: 42 })
+        ));
+    }
+
+    #[test]
+    fn test_read_frame_unknown_id() {
+        let bytes: &[u8] = &[1, 0x7f];
+        let result = Packet::read_frame(State::Login, Direction::ClientBound, &mut &bytes[..]);
+        let error = result.unwrap_err().downcast::<FrameError>().unwrap();
+        assert!(matches!(
+            error,
+            FrameError::UnknownPacket {
+                state: State::Login,
+                direction: Direction::ClientBound,
+                id: 0x7f
+            }
+        ));
+    }
+
+    #[test]
+    fn test_read_frame_consecutive() {
+        let mut vec = vec![];
+        Packet::KeepAliveRequest(KeepAliveRequest { keep_alive_id: 7 })
+            .write_frame(&mut vec)
+            .unwrap();
+        Packet::Tags(Tags::default()).write_frame(&mut vec).unwrap();
+
+        let mut reader = &vec[..];
+        let first = Packet::read_frame(State::Play, Direction::ClientBound, &mut reader).unwrap();
+        let second = Packet::read_frame(State::Play, Direction::ClientBound, &mut reader).unwrap();
+        assert!(matches!(
+            first,
+            Packet::KeepAliveRequest(KeepAliveRequest { keep_alive_id: 7 })
+        ));
+        assert!(matches!(second, Packet::Tags(_)));
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_packet_ids() {
+        assert_eq!(
+            Packet::LoginStart(LoginStart::default()).id(),
+            (State::Login, Direction::ServerBound, VarInt(0x00))
+        );
+        assert_eq!(
+            Packet::Tags(Tags::default()).id(),
+            (State::Play, Direction::ClientBound, VarInt(0x67))
+        );
+        assert_eq!(
+            Packet::UseItem(UseItem::default()).id(),
+            (State::Play, Direction::ServerBound, VarInt(0x2F))
+        );
+    }
+
+    fn protocol_error(state: State, direction: Direction, payload: &[u8]) -> ProtocolError {
+        Packet::read_payload(state, direction, payload)
+            .unwrap_err()
+            .downcast()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let error = protocol_error(
+            State::Handshake,
+            Direction::ServerBound,
+            b"\x00\xf6\x05\x09local",
+        );
+        assert_eq!(error.packet, "Handshake");
+        assert_eq!(error.id, 0);
+        assert_eq!(error.field, Some("server_address"));
+        assert_eq!(error.offset, 3);
+        assert_eq!(error.kind, ProtocolErrorKind::Truncated);
+
+        let error = protocol_error(
+            State::Handshake,
+            Direction::ServerBound,
+            b"\x00\xff\xff\xff\xff\xff\x01",
+        );
+        assert_eq!(error.field, Some("protocol_version"));
+        assert_eq!(error.offset, 1);
+        assert_eq!(error.kind, ProtocolErrorKind::Oversize);
+
+        let error = protocol_error(State::Play, Direction::ClientBound, b"\x36\x09\x00");
+        assert_eq!(error.packet, "PlayerInfo");
+        assert_eq!(error.field, Some("action"));
+        assert_eq!(error.kind, ProtocolErrorKind::InvalidEnum);
+
+        let error = protocol_error(
+            State::Status,
+            Direction::ClientBound,
+            &[1, 0, 0, 0, 0, 0, 0, 0, 42, 7, 7],
+        );
+        assert_eq!(error.packet, "StatusPong");
+        assert_eq!(error.field, None);
+        assert_eq!(error.offset, 9);
+        assert_eq!(error.kind, ProtocolErrorKind::TrailingBytes(2));
+        assert_eq!(
+            error.to_string(),
+            "Status ClientBound packet StatusPong (0x01) at byte 9: TrailingBytes(2)"
+        );
+    }
+
+    #[test]
+    fn test_field_string_limits() {
+        let mut payload = vec![];
+        Packet::LoginStart(LoginStart {
+            username: "a".repeat(17),
+        })
+        .write_payload(&mut payload)
+        .unwrap();
+        let error = protocol_error(State::Login, Direction::ServerBound, &payload);
+        assert_eq!(error.field, Some("username"));
+        assert_eq!(error.kind, ProtocolErrorKind::Oversize);
+
+        // Chat messages the server sends may be longer than those it accepts.
+        let text = "a".repeat(300);
+        let (_, read) = round_trip(
+            Packet::ChatMessage(ChatMessage {
+                chat_json: Chat(text.clone()),
+                ..ChatMessage::default()
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert!(matches!(read, Packet::ChatMessage(message) if message.chat_json.0 == text));
+        payload.clear();
+        Packet::SendChatMessage(SendChatMessage {
+            message: text.clone(),
+        })
+        .write_payload(&mut payload)
+        .unwrap();
+        let error = protocol_error(State::Play, Direction::ServerBound, &payload);
+        assert_eq!(error.field, Some("message"));
+        assert_eq!(error.kind, ProtocolErrorKind::Oversize);
+
+        payload.clear();
+        Packet::ClientSettings(ClientSettings {
+            locale: "a".repeat(17),
+            ..ClientSettings::default()
+        })
+        .write_payload(&mut payload)
+        .unwrap();
+        let error = protocol_error(State::Play, Direction::ServerBound, &payload);
+        assert_eq!(error.field, Some("locale"));
+        assert_eq!(error.kind, ProtocolErrorKind::Oversize);
+
+        payload.clear();
+        Packet::Teams(Teams {
+            team_name: "red".to_owned(),
+            mode: 0,
+            display_name: Some(Chat("\"Red\"".to_owned())),
+            friendly_flags: Some(0),
+            name_tag_visibility: Some("a".repeat(41)),
+            collision_rule: Some("always".to_owned()),
+            team_color: Some(TeamColor::Red),
+            prefix: Some(Chat("\"\"".to_owned())),
+            suffix: Some(Chat("\"\"".to_owned())),
+            entities: Some(vec![]),
+        })
+        .write_payload(&mut payload)
+        .unwrap();
+        let error = protocol_error(State::Play, Direction::ClientBound, &payload);
+        assert_eq!(error.field, Some("name_tag_visibility"));
+        assert_eq!(error.kind, ProtocolErrorKind::Oversize);
+    }
+
+    fn round_trip(packet: Packet, state: State, direction: Direction) -> (Vec<u8>, Packet) {
+        let mut payload = vec![];
+        packet.write_payload(&mut payload).unwrap();
+        let read = Packet::read_payload(state, direction, &payload).unwrap();
+        (payload, read)
+    }
+
+    #[test]
+    fn test_conditional_fields() {
+        // Only the sound is sent when only flag 0x02 is set.
+        let (payload, read) = round_trip(
+            Packet::StopSound(StopSound {
+                flags: 0x02,
+                source: None,
+                sound: Some(Identifier("minecraft:music.game".to_owned())),
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(payload, b"\x5e\x02\x14minecraft:music.game");
+        let Packet::StopSound(read) = read else {
+            panic!("expected StopSound");
+        };
+        assert_eq!(read.source, None);
+        assert_eq!(
+            read.sound,
+            Some(Identifier("minecraft:music.game".to_owned()))
+        );
+
+        // Removing a score sends no value.
+        let (payload, read) = round_trip(
+            Packet::UpdateScore(UpdateScore {
+                entity_name: "a".to_owned(),
+                action: VarInt(1),
+                objective_name: "b".to_owned(),
+                value: Some(VarInt(5)),
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(payload, b"\x56\x01a\x01\x01b");
+        let Packet::UpdateScore(read) = read else {
+            panic!("expected UpdateScore");
+        };
+        assert_eq!(read.value, None);
+
+        // A map without icons or a data update.
+        let (payload, read) = round_trip(
+            Packet::MapData(MapData {
+                map_id: VarInt(3),
+                ..MapData::default()
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(payload, b"\x27\x03\x00\x00\x00\x00");
+        let Packet::MapData(read) = read else {
+            panic!("expected MapData");
+        };
+        assert!(read.icons.is_none() && read.data.is_none());
+
+        // An attack has neither a target position nor a hand.
+        let (payload, read) = round_trip(
+            Packet::InteractEntity(InteractEntity {
+                entity_id: VarInt(9),
+                interaction_type: InteractionType::Attack,
+                sneaking: true,
+                ..InteractEntity::default()
+            }),
+            State::Play,
+            Direction::ServerBound,
+        );
+        assert_eq!(payload, b"\x0d\x09\x01\x01");
+        let Packet::InteractEntity(read) = read else {
+            panic!("expected InteractEntity");
+        };
+        assert!(read.sneaking);
+    }
+
+    #[test]
+    fn test_game_mode_fields() {
+        // A player with no previous game mode, sent as -1.
+        let (payload, read) = round_trip(
+            Packet::Respawn(Respawn {
+                gamemode: AsByte(GameMode::Creative),
+                previous_gamemode: PreviousGameMode::None,
+                ..Respawn::default()
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert!(payload.ends_with(b"\x01\xff\x00\x00\x00"));
+        let Packet::Respawn(read) = read else {
+            panic!("expected Respawn");
+        };
+        assert_eq!(read.gamemode, AsByte(GameMode::Creative));
+        assert_eq!(read.previous_gamemode, PreviousGameMode::None);
+
+        // Closing the advancements screen sends no tab.
+        let (payload, read) = round_trip(
+            Packet::AdvancementTab(AdvancementTab {
+                action: AdvancementTabAction::ClosedScreen,
+                tab_id: None,
+            }),
+            State::Play,
+            Direction::ServerBound,
+        );
+        assert_eq!(payload, b"\x22\x01");
+        let Packet::AdvancementTab(read) = read else {
+            panic!("expected AdvancementTab");
+        };
+        assert_eq!(read.action, AdvancementTabAction::ClosedScreen);
+    }
+
+    #[test]
+    fn test_vibration_signal_destinations() {
+        // As a 1.18.2 server sends them: from (1, 64, 2) to entity 5 and to
+        // the block at (1, 64, 2), arriving in 20 ticks.
+        let mut payload = b"\x05\x00\x00\x00\x40\x00\x00\x20\x40".to_vec();
+        payload.extend(b"\x10minecraft:entity\x05\x14");
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap();
+        let Packet::SkulkVibrationSignal(signal) = packet else {
+            panic!("expected SkulkVibrationSignal");
+        };
+        assert_eq!(signal.destination_entity, Some(VarInt(5)));
+        assert_eq!(signal.destination_position, None);
+        assert_eq!(signal.arrival_ticks, VarInt(20));
+
+        let mut payload = b"\x05\x00\x00\x00\x40\x00\x00\x20\x40".to_vec();
+        payload.extend(b"\x0fminecraft:block\x00\x00\x00\x40\x00\x00\x20\x40\x14");
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap();
+        let Packet::SkulkVibrationSignal(signal) = packet else {
+            panic!("expected SkulkVibrationSignal");
+        };
+        assert_eq!(signal.destination_entity, None);
+        assert_eq!(
+            signal.destination_position,
+            Some(Position { x: 1, y: 64, z: 2 })
+        );
+        assert_eq!(signal.arrival_ticks, VarInt(20));
+    }
+
+    #[test]
+    fn test_particle_data() {
+        // A red dust particle of scale 1 and a stone block particle.
+        let header = |id: u8| {
+            let mut payload = vec![0x24, 0, 0, 0, id, 0];
+            payload.extend([0; 24 + 12 + 4]);
+            payload.extend([0, 0, 0, 1]);
+            payload
+        };
+        let mut payload = header(14);
+        payload.extend(b"\x3f\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x3f\x80\x00\x00");
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap();
+        let Packet::Particle(particle) = packet else {
+            panic!("expected Particle");
+        };
+        assert_eq!(
+            particle.data,
+            ParticleData::Dust {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.0,
+                scale: 1.0,
+            }
+        );
+
+        let mut payload = header(2);
+        payload.push(1);
+        let (written, read) = round_trip(
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap(),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(written, payload);
+        let Packet::Particle(particle) = read else {
+            panic!("expected Particle");
+        };
+        assert_eq!(particle.data, ParticleData::BlockState(VarInt(1)));
+
+        // Particles without data end after the count.
+        let payload = header(0);
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap();
+        let Packet::Particle(particle) = packet else {
+            panic!("expected Particle");
+        };
+        assert_eq!(particle.data, ParticleData::None);
+    }
+
+    #[test]
+    fn test_prefixed_optionals() {
+        let icon = (
+            VarInt(0),
+            1,
+            2,
+            3,
+            PrefixedOption(Some(Chat("\"x\"".to_owned()))),
+        );
+        let (_, read) = round_trip(
+            Packet::MapData(MapData {
+                tracking_position: true,
+                icons: Some(vec![
+                    icon.clone(),
+                    (VarInt(1), 4, 5, 6, PrefixedOption(None)),
+                ]),
+                columns: 1,
+                rows: Some(1),
+                x: Some(0),
+                z: Some(0),
+                data: Some(vec![42]),
+                ..MapData::default()
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        let Packet::MapData(read) = read else {
+            panic!("expected MapData");
+        };
+        let icons = read.icons.unwrap();
+        assert_eq!(icons[0], icon);
+        assert_eq!(icons[1].4, PrefixedOption(None));
+        assert_eq!(read.data, Some(vec![42]));
+
+        let (payload, read) = round_trip(
+            Packet::SelectAdvancement(SelectAdvancement {
+                has_id: false,
+                identifier: None,
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(payload, b"\x40\x00");
+        let Packet::SelectAdvancement(read) = read else {
+            panic!("expected SelectAdvancement");
+        };
+        assert_eq!(read.identifier, None);
+    }
+
+    #[test]
+    fn test_array_lengths() {
+        let (payload, _) = round_trip(
+            Packet::DestroyEntities(DestroyEntities {
+                entity_ids: vec![VarInt(1), VarInt(300)],
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(payload, b"\x3a\x02\x01\xac\x02");
+
+        let (payload, read) = round_trip(
+            Packet::WindowItems(WindowItems {
+                window_id: 1,
+                state_id: VarInt(2),
+                slot_data: vec![Slot::Nothing; 3],
+                carried_item: Slot::Nothing,
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(payload, b"\x14\x01\x02\x03\x00\x00\x00\x00");
+        let Packet::WindowItems(read) = read else {
+            panic!("expected WindowItems");
+        };
+        assert_eq!(read.slot_data.len(), 3);
+
+        let (payload, read) = round_trip(
+            Packet::Explosion(Explosion {
+                records: vec![(-1, 0, 1)],
+                player_motion: (0.0, 1.0, 0.0),
+                ..Explosion::default()
+            }),
+            State::Play,
+            Direction::ClientBound,
+        );
+        assert_eq!(
+            &payload[..20],
+            &[0x1c, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0xff, 0]
+        );
+        assert_eq!(payload.len(), 33);
+        let Packet::Explosion(read) = read else {
+            panic!("expected Explosion");
+        };
+        assert_eq!(read.records, [(-1, 0, 1)]);
+        assert_eq!(read.player_motion, (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_edit_book_encoding() {
+        // The page count is the length prefix of the entries, sent once.
+        let (payload, read) = round_trip(
+            Packet::EditBook(EditBook {
+                slot: VarInt(0),
+                entries: vec!["hi".to_owned(), "there".to_owned()],
+                has_title: true,
+                title: Some("Log".to_owned()),
+            }),
+            State::Play,
+            Direction::ServerBound,
+        );
+        assert_eq!(payload, b"\x0b\x00\x02\x02hi\x05there\x01\x03Log");
+        let Packet::EditBook(read) = read else {
+            panic!("expected EditBook");
+        };
+        assert_eq!(read.entries, ["hi", "there"]);
+        assert_eq!(read.title.as_deref(), Some("Log"));
+    }
+
+    #[test]
+    fn test_entity_packet_layouts() {
+        // As a 1.18.2 server sends them, each using its whole payload.
+        let payload = b"\x2b\x96\x01\x40\xe0\x01";
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, payload).unwrap();
+        let Packet::EntityRotation(rotation) = packet else {
+            panic!("expected EntityRotation");
+        };
+        assert_eq!(rotation.entity_id, VarInt(150));
+        assert!(rotation.on_ground);
+
+        // Speed II for 30 seconds, with particles and an icon.
+        let payload = b"\x65\x96\x01\x01\x01\xd8\x04\x06";
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, payload).unwrap();
+        let Packet::EntityEffect(effect) = packet else {
+            panic!("expected EntityEffect");
+        };
+        assert_eq!(effect.entity_id, VarInt(150));
+        assert_eq!((effect.effect_id, effect.amplifier), (1, 1));
+        assert_eq!(effect.duration, VarInt(600));
+        assert_eq!(effect.flags, 6);
+
+        // A zombie at the origin, facing south with its head turned.
+        let mut payload = b"\x02\x96\x01".to_vec();
+        payload.extend([0; 16]);
+        payload.push(107);
+        payload.extend([0; 24]);
+        payload.extend([0, 0, 0x40]);
+        payload.extend([0, 0, 0, 0, 0, 0]);
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap();
+        let Packet::SpawnLivingEntity(spawn) = packet else {
+            panic!("expected SpawnLivingEntity");
+        };
+        assert_eq!(spawn.entity_type, VarInt(107));
+        assert!((spawn.head_yaw.0 - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(spawn.velocity_z, 0);
+    }
+
+    #[test]
+    fn test_block_entity_without_data() {
+        // A sign at (1, 64, 2) whose data the server leaves out.
+        let payload = b"\x0a\x00\x00\x00\x40\x00\x00\x20\x40\x07\x00";
+        let packet = Packet::read_payload(State::Play, Direction::ClientBound, payload).unwrap();
+        let Packet::BlockEntityData(block_entity) = packet else {
+            panic!("expected BlockEntityData");
+        };
+        assert_eq!(block_entity.entity_type, VarInt(7));
+        assert_eq!(block_entity.nbt_data, Nbt::default());
+    }
@@ -0,0 +1,299 @@
+use crate::network::enums::GameMode;
+use crate::network::packets::PlayerInfo;
+use crate::network::read_write::{check_array_length, read_optional, write_optional};
+use crate::network::{Chat, Index, MinecraftIo, VarInt};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use uuid::Uuid;
+
+#[derive(Copy, Clone, Debug)]
+pub enum PlayerInfoError {
+    UnknownAction(i32),
+}
+
+impl Display for PlayerInfoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for PlayerInfoError {}
+
+/// A property of a player's profile, such as their skin `textures`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+impl MinecraftIo for PlayerProperty {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.name.minecraft_write(writer)?;
+        self.value.minecraft_write(writer)?;
+        write_optional(&self.signature, writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            name: String::minecraft_read(reader)?,
+            value: String::minecraft_read(reader)?,
+            signature: read_optional(reader)?,
+        })
+    }
+}
+
+/// A player in the tab list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerListEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    pub properties: Vec<PlayerProperty>,
+    pub game_mode: GameMode,
+    pub ping: VarInt,
+    pub display_name: Option<Chat>,
+}
+
+impl MinecraftIo for PlayerListEntry {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.uuid.minecraft_write(writer)?;
+        self.name.minecraft_write(writer)?;
+        self.properties.minecraft_write(writer)?;
+        self.game_mode.minecraft_write(writer)?;
+        self.ping.minecraft_write(writer)?;
+        write_optional(&self.display_name, writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            uuid: Uuid::minecraft_read(reader)?,
+            name: String::minecraft_read(reader)?,
+            properties: <Vec<PlayerProperty>>::minecraft_read(reader)?,
+            game_mode: GameMode::minecraft_read(reader)?,
+            ping: VarInt::minecraft_read(reader)?,
+            display_name: read_optional(reader)?,
+        })
+    }
+}
+
+/// The action of a `PlayerInfo` packet, which decides what is sent for each
+/// player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlayerInfoAction {
+    AddPlayer(Vec<PlayerListEntry>),
+    UpdateGameMode(Vec<(Uuid, GameMode)>),
+    UpdateLatency(Vec<(Uuid, VarInt)>),
+    UpdateDisplayName(Vec<(Uuid, Option<Chat>)>),
+    RemovePlayer(Vec<Uuid>),
+}
+
+impl Default for PlayerInfoAction {
+    fn default() -> Self {
+        Self::AddPlayer(Vec::new())
+    }
+}
+
+impl PlayerInfoAction {
+    pub fn id(&self) -> i32 {
+        match self {
+            Self::AddPlayer(_) => 0,
+            Self::UpdateGameMode(_) => 1,
+            Self::UpdateLatency(_) => 2,
+            Self::UpdateDisplayName(_) => 3,
+            Self::RemovePlayer(_) => 4,
+        }
+    }
+}
+
+impl MinecraftIo for PlayerInfoAction {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        VarInt(self.id()).minecraft_write(writer)?;
+        match self {
+            Self::AddPlayer(players) => players.minecraft_write(writer),
+            Self::UpdateGameMode(players) => players.minecraft_write(writer),
+            Self::UpdateLatency(players) => players.minecraft_write(writer),
+            Self::UpdateDisplayName(players) => {
+                VarInt(players.len() as i32).minecraft_write(writer)?;
+                for (uuid, display_name) in players {
+                    uuid.minecraft_write(writer)?;
+                    write_optional(display_name, writer)?;
+                }
+                Ok(())
+            }
+            Self::RemovePlayer(players) => players.minecraft_write(writer),
+        }
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(match VarInt::minecraft_read(reader)?.0 {
+            0 => Self::AddPlayer(<Vec<PlayerListEntry>>::minecraft_read(reader)?),
+            1 => Self::UpdateGameMode(<Vec<(Uuid, GameMode)>>::minecraft_read(reader)?),
+            2 => Self::UpdateLatency(<Vec<(Uuid, VarInt)>>::minecraft_read(reader)?),
+            3 => {
+                let length = check_array_length(VarInt::minecraft_read(reader)?.into_index()?)?;
+                let mut players = Vec::new();
+                for _ in 0..length {
+                    players.push((Uuid::minecraft_read(reader)?, read_optional(reader)?));
+                }
+                Self::UpdateDisplayName(players)
+            }
+            4 => Self::RemovePlayer(<Vec<Uuid>>::minecraft_read(reader)?),
+            action => Err(PlayerInfoError::UnknownAction(action))?,
+        })
+    }
+}
+
+/// The players in the tab list, kept up to date from `PlayerInfo` packets.
+#[derive(Clone, Debug, Default)]
+pub struct PlayerList {
+    players: HashMap<Uuid, PlayerListEntry>,
+}
+
+impl PlayerList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, uuid: &Uuid) -> Option<&PlayerListEntry> {
+        self.players.get(uuid)
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &PlayerListEntry> {
+        self.players.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// Updates to players that were never added are ignored.
+    pub fn apply(&mut self, packet: &PlayerInfo) {
+        match &packet.action {
+            PlayerInfoAction::AddPlayer(players) => {
+                for player in players {
+                    self.players.insert(player.uuid, player.clone());
+                }
+            }
+            PlayerInfoAction::UpdateGameMode(players) => {
+                for (uuid, game_mode) in players {
+                    if let Some(player) = self.players.get_mut(uuid) {
+                        player.game_mode = *game_mode;
+                    }
+                }
+            }
+            PlayerInfoAction::UpdateLatency(players) => {
+                for (uuid, ping) in players {
+                    if let Some(player) = self.players.get_mut(uuid) {
+                        player.ping = *ping;
+                    }
+                }
+            }
+            PlayerInfoAction::UpdateDisplayName(players) => {
+                for (uuid, display_name) in players {
+                    if let Some(player) = self.players.get_mut(uuid) {
+                        player.display_name = display_name.clone();
+                    }
+                }
+            }
+            PlayerInfoAction::RemovePlayer(players) => {
+                for uuid in players {
+                    self.players.remove(uuid);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::limits::LimitError;
+    use crate::network::{ArrayError, Direction, Limits, Packet, State};
+
+    fn round_trip(action: PlayerInfoAction) -> PlayerInfo {
+        let mut payload = vec![];
+        Packet::PlayerInfo(PlayerInfo { action })
+            .write_payload(&mut payload)
+            .unwrap();
+        let Packet::PlayerInfo(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected PlayerInfo");
+        };
+        read
+    }
+
+    #[test]
+    fn test_player_list() {
+        let uuid = Uuid::from_u128(1);
+        let entry = PlayerListEntry {
+            uuid,
+            name: "Notch".to_owned(),
+            properties: vec![PlayerProperty {
+                name: "textures".to_owned(),
+                value: "e30=".to_owned(),
+                signature: Some("c2ln".to_owned()),
+            }],
+            game_mode: GameMode::Creative,
+            ping: VarInt(20),
+            display_name: None,
+        };
+        let actions = [
+            PlayerInfoAction::AddPlayer(vec![entry.clone()]),
+            PlayerInfoAction::UpdateGameMode(vec![(uuid, GameMode::Spectator)]),
+            PlayerInfoAction::UpdateLatency(vec![(uuid, VarInt(150))]),
+            PlayerInfoAction::UpdateDisplayName(vec![(uuid, Some(Chat("\"Steve\"".to_owned())))]),
+            PlayerInfoAction::UpdateGameMode(vec![(Uuid::from_u128(2), GameMode::Survival)]),
+        ];
+
+        let mut list = PlayerList::new();
+        for action in actions {
+            let packet = round_trip(action.clone());
+            assert_eq!(packet.action, action);
+            list.apply(&packet);
+        }
+        assert_eq!(
+            list.get(&uuid),
+            Some(&PlayerListEntry {
+                game_mode: GameMode::Spectator,
+                ping: VarInt(150),
+                display_name: Some(Chat("\"Steve\"".to_owned())),
+                ..entry
+            })
+        );
+        assert_eq!(list.len(), 1);
+
+        list.apply(&round_trip(PlayerInfoAction::RemovePlayer(vec![uuid])));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_display_name_count() {
+        let negative = [3, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        let error = PlayerInfoAction::minecraft_read(&mut &negative[..]).unwrap_err();
+        assert!(error.is::<ArrayError>());
+
+        let mut bytes = vec![];
+        PlayerInfoAction::UpdateDisplayName(vec![(Uuid::from_u128(1), None); 3])
+            .minecraft_write(&mut bytes)
+            .unwrap();
+        let limits = Limits {
+            max_array_length: 2,
+            ..Limits::DEFAULT
+        };
+        let error = limits
+            .scope(|| PlayerInfoAction::minecraft_read(&mut &bytes[..]))
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<LimitError>().unwrap(),
+            LimitError::ArrayTooLong { length: 3, max: 2 }
+        );
+    }
+}
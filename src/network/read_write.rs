@@ -1,19 +1,36 @@
 use super::Position;
+use crate::network::enums::ModifierOperation;
+use crate::network::limits::{LimitError, Limits};
 use crate::network::varint::{VarInt, VarLong};
 use crate::network::{
-    Angle, ByteArray, Chat, Identifier, Index, LengthPrefixedArray, LengthPrefixedByteArray, Nbt,
-    Slot,
+    Angle, AttributeModifier, BitSet, ByteArray, Chat, EntityProperty, Equipment, Identifier,
+    Index, LengthPrefixedArray, LengthPrefixedByteArray, Nbt, PrefixedOption, Slot, Statistic,
 };
 use anyhow::Result;
 use bytemuck::pod_align_to;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::error::Error;
 use std::f32::consts::PI;
+use std::fmt::{Display, Formatter};
 use std::io::{Read, Write};
 use uuid::Uuid;
 
 #[cfg(test)]
 mod tests;
 
+#[derive(Copy, Clone, Debug)]
+pub enum ArrayError {
+    NegativeLength(i64),
+}
+
+impl Display for ArrayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for ArrayError {}
+
 pub trait MinecraftIo
 where
     Self: Sized,
@@ -104,7 +121,7 @@ impl MinecraftIo for VarInt {
 
 impl MinecraftIo for VarLong {
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
-        Ok(self.write(writer)?)
+        self.write(writer)
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
@@ -112,19 +129,67 @@ impl MinecraftIo for VarLong {
     }
 }
 
+/// Reads a VarInt length prefix.
+fn read_length(reader: &mut impl Read) -> Result<usize> {
+    VarInt::read(reader)?.into_index()
+}
+
+/// Checks an array length against the current limits.
+pub(crate) fn check_array_length(length: usize) -> Result<usize> {
+    let max = Limits::current().max_array_length;
+    if length > max {
+        Err(LimitError::ArrayTooLong { length, max })?;
+    }
+    Ok(length)
+}
+
+/// Reads `length` bytes, allocating only as much as the reader actually
+/// holds, so a bogus length fails instead of reserving gigabytes.
+fn read_bytes(reader: &mut impl Read, length: usize) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(length as u64).read_to_end(&mut buf)?;
+    if buf.len() != length {
+        Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+    }
+    Ok(buf)
+}
+
 impl MinecraftIo for String {
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
         VarInt(self.len() as i32).minecraft_write(writer)?;
-        writer.write(self.as_bytes())?;
+        writer.write_all(self.as_bytes())?;
         Ok(())
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
-        let length = VarInt::read(reader)?;
-        let mut data = vec![0u8; length.0 as usize];
-        reader.read(&mut data)?;
-        Ok(String::from_utf8(data)?)
+        read_string(reader, Limits::current().max_string_length)
+    }
+}
+
+/// Reads a string of at most `max` code points.
+fn read_string(reader: &mut impl Read, max: usize) -> Result<String> {
+    let length = read_length(reader)?;
+    check_string_bytes(length, max)?;
+    let string = String::from_utf8(read_bytes(reader, length)?)?;
+    check_string(&string, max)?;
+    Ok(string)
+}
+
+/// Rejects a string length prefix too long for `max` code points before
+/// anything is read. A code point takes at most four bytes.
+pub(crate) fn check_string_bytes(length: usize, max: usize) -> Result<()> {
+    if length > max.saturating_mul(4) {
+        Err(LimitError::StringTooLong { length, max })?;
     }
+    Ok(())
+}
+
+pub(crate) fn check_string(string: &str, max: usize) -> Result<()> {
+    let length = string.chars().count();
+    if length > max {
+        Err(LimitError::StringTooLong { length, max })?;
+    }
+    Ok(())
 }
 
 const POSITION_XZ_MASK: i32 = 0x3FF_FFFF;
@@ -148,8 +213,12 @@ impl MinecraftIo for Position {
     }
 }
 
+/// Arrays prefixed with their length as a VarInt, the usual representation.
+/// Other prefixes use `LengthPrefixedArray`, fixed counts use arrays, and
+/// bytes running to the end of the packet use `ByteArray`.
 impl<T: MinecraftIo> MinecraftIo for Vec<T> {
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        VarInt(self.len() as i32).minecraft_write(writer)?;
         for value in self {
             value.minecraft_write(writer)?;
         }
@@ -157,24 +226,135 @@ impl<T: MinecraftIo> MinecraftIo for Vec<T> {
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let length = check_array_length(read_length(reader)?)?;
         let mut vec = Vec::new();
-        while let Ok(value) = <T>::minecraft_read(reader) {
-            vec.push(value);
+        for _ in 0..length {
+            vec.push(<T>::minecraft_read(reader)?);
         }
         Ok(vec)
     }
 }
 
+/// A fixed number of values with no length prefix.
+impl<T: MinecraftIo, const N: usize> MinecraftIo for [T; N] {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        for value in self {
+            value.minecraft_write(writer)?;
+        }
+        Ok(())
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let mut vec = Vec::with_capacity(N);
+        for _ in 0..N {
+            vec.push(<T>::minecraft_read(reader)?);
+        }
+        match vec.try_into() {
+            Ok(array) => Ok(array),
+            Err(_) => unreachable!("read exactly {} values", N),
+        }
+    }
+}
+
+impl MinecraftIo for Statistic {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.category_id.minecraft_write(writer)?;
+        self.statistic_id.minecraft_write(writer)?;
+        self.value.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            category_id: VarInt::minecraft_read(reader)?,
+            statistic_id: VarInt::minecraft_read(reader)?,
+            value: VarInt::minecraft_read(reader)?,
+        })
+    }
+}
+
+impl MinecraftIo for EntityProperty {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.key.minecraft_write(writer)?;
+        self.value.minecraft_write(writer)?;
+        self.modifiers.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            key: Identifier::minecraft_read(reader)?,
+            value: f64::minecraft_read(reader)?,
+            modifiers: <Vec<AttributeModifier>>::minecraft_read(reader)?,
+        })
+    }
+}
+
+impl MinecraftIo for AttributeModifier {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.uuid.minecraft_write(writer)?;
+        self.amount.minecraft_write(writer)?;
+        self.operation.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            uuid: Uuid::minecraft_read(reader)?,
+            amount: f64::minecraft_read(reader)?,
+            operation: ModifierOperation::minecraft_read(reader)?,
+        })
+    }
+}
+
+const EQUIPMENT_HAS_NEXT: i8 = i8::MIN;
+
+impl MinecraftIo for Equipment {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        for (i, (slot, item)) in self.0.iter().enumerate() {
+            let has_next = i + 1 < self.0.len();
+            let slot = if has_next {
+                slot | EQUIPMENT_HAS_NEXT
+            } else {
+                *slot
+            };
+            slot.minecraft_write(writer)?;
+            item.minecraft_write(writer)?;
+        }
+        Ok(())
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let mut equipment = Vec::new();
+        loop {
+            let slot = i8::minecraft_read(reader)?;
+            equipment.push((slot & !EQUIPMENT_HAS_NEXT, Slot::minecraft_read(reader)?));
+            if slot & EQUIPMENT_HAS_NEXT == 0 {
+                return Ok(Self(equipment));
+            }
+        }
+    }
+}
+
+impl MinecraftIo for BitSet {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.0.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self(<Vec<u64>>::minecraft_read(reader)?))
+    }
+}
+
+/// The rest of the packet body. Packets are decoded from their frame alone, so
+/// this never reads into the next packet.
 impl MinecraftIo for ByteArray {
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
-        writer.write(self)?;
+        writer.write_all(self)?;
         Ok(())
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        return Ok(Self(buf));
+        Ok(Self(buf))
     }
 }
 
@@ -195,8 +375,8 @@ where
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
         let protocol_length = <T>::minecraft_read(reader)?;
-        let length = protocol_length.into_index();
-        let mut buf = Vec::with_capacity(length);
+        let length = check_array_length(protocol_length.into_index()?)?;
+        let mut buf = Vec::new();
         for _ in 0..length {
             buf.push(<U>::minecraft_read(reader)?);
         }
@@ -212,19 +392,20 @@ where
         let length = self.value.len();
         let protocol_length = <T>::to_value(length);
         protocol_length.minecraft_write(writer)?;
-        writer.write(&self.value)?;
+        writer.write_all(&self.value)?;
         Ok(())
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
         let protocol_length = <T>::minecraft_read(reader)?;
-        let length = protocol_length.into_index();
-        let mut buf = vec![0; length];
-        reader.read(&mut buf);
-        Ok(Self::from_vec(buf))
+        let length = check_array_length(protocol_length.into_index()?)?;
+        Ok(Self::from_vec(read_bytes(reader, length)?))
     }
 }
 
+/// For packet fields with a `when` condition, which decides whether the value
+/// is sent, so a value is always read. See `PrefixedOption` for optionals
+/// that carry their own presence flag.
 impl<T: MinecraftIo> MinecraftIo for Option<T> {
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
         if self.is_some() {
@@ -239,13 +420,43 @@ impl<T: MinecraftIo> MinecraftIo for Option<T> {
     }
 }
 
+/// Reads an optional value sent after a boolean saying whether it is present.
+pub(crate) fn read_optional<T: MinecraftIo>(reader: &mut impl Read) -> Result<Option<T>> {
+    if bool::minecraft_read(reader)? {
+        Ok(Some(T::minecraft_read(reader)?))
+    } else {
+        Ok(None)
+    }
+}
+
+pub(crate) fn write_optional<T: MinecraftIo>(
+    value: &Option<T>,
+    writer: &mut impl Write,
+) -> Result<()> {
+    value.is_some().minecraft_write(writer)?;
+    value.minecraft_write(writer)
+}
+
+impl<T: MinecraftIo> MinecraftIo for PrefixedOption<T> {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        write_optional(&self.0, writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self(read_optional(reader)?))
+    }
+}
+
 impl MinecraftIo for Chat {
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
         self.0.minecraft_write(writer)
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
-        Ok(Self(<String>::minecraft_read(reader)?))
+        Ok(Self(read_string(
+            reader,
+            Limits::current().max_chat_length,
+        )?))
     }
 }
 
@@ -283,27 +494,155 @@ impl MinecraftIo for Uuid {
 
 // Possibility: replace Nbt with nbt::Blob and impl this on that.
 impl MinecraftIo for Nbt {
+    // A lone TAG_End stands for no tag at all, like a block entity without
+    // data. It reads as an empty blob, which is written back the same way.
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        if *self == Self::default() {
+            return NBT_END.minecraft_write(writer);
+        }
         self.0.minecraft_write(writer)
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
-        let nbt = nbt::from_reader(reader)?;
-        Ok(Self(nbt))
+        let tag = <u8>::minecraft_read(reader)?;
+        if tag == NBT_END {
+            return Ok(Self::default());
+        }
+        Ok(Self(nbt::Blob::minecraft_read(&mut [tag].chain(reader))?))
     }
 }
 
 impl MinecraftIo for nbt::Blob {
+    // The blob's own reader and writer keep each tag's type, which going
+    // through serde does not.
     fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
         let mut tmp = vec![];
-        nbt::to_writer(&mut tmp, self, None)?;
-        writer.write(&tmp)?;
+        self.to_writer(&mut tmp)?;
+        writer.write_all(&tmp)?;
         Ok(())
     }
 
     fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
-        let nbt = nbt::from_reader(reader)?;
-        Ok(nbt)
+        let bytes = read_nbt_bytes(reader)?;
+        Ok(nbt::Blob::from_reader(&mut &bytes[..])?)
+    }
+}
+
+/// Copies one named NBT tag out of `reader`, checking it against the NBT
+/// limits first: hematite reserves space for arrays and lists from their
+/// lengths on the wire.
+fn read_nbt_bytes(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let limits = Limits::current();
+    let mut scanner = NbtScanner {
+        reader,
+        bytes: Vec::new(),
+        max_depth: limits.max_nbt_depth,
+        max_size: limits.max_nbt_size,
+    };
+    let tag = scanner.copy(1)?[0];
+    if tag != NBT_END {
+        scanner.string()?;
+        scanner.payload(tag, 0)?;
+    }
+    Ok(scanner.bytes)
+}
+
+const NBT_END: u8 = 0;
+const NBT_LIST: u8 = 9;
+const NBT_COMPOUND: u8 = 10;
+
+#[derive(Copy, Clone, Debug)]
+pub enum NbtError {
+    InvalidTag(u8),
+}
+
+impl Display for NbtError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for NbtError {}
+
+/// Walks an NBT tag without building it, keeping the bytes it reads.
+struct NbtScanner<'a, R> {
+    reader: &'a mut R,
+    bytes: Vec<u8>,
+    max_depth: usize,
+    max_size: usize,
+}
+
+impl<R: Read> NbtScanner<'_, R> {
+    fn copy(&mut self, length: usize) -> Result<&[u8]> {
+        let start = self.bytes.len();
+        if length > self.max_size - start {
+            Err(LimitError::NbtTooLarge(self.max_size))?;
+        }
+        (&mut *self.reader)
+            .take(length as u64)
+            .read_to_end(&mut self.bytes)?;
+        if self.bytes.len() - start != length {
+            Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+        }
+        Ok(&self.bytes[start..])
+    }
+
+    fn length(&mut self) -> Result<usize> {
+        let length = i32::from_be_bytes(self.copy(4)?.try_into()?);
+        if length < 0 {
+            Err(ArrayError::NegativeLength(length as i64))?;
+        }
+        Ok(length as usize)
+    }
+
+    fn array(&mut self, element_size: usize) -> Result<()> {
+        let length = self.length()?;
+        self.copy(length.saturating_mul(element_size))?;
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<()> {
+        let length = u16::from_be_bytes(self.copy(2)?.try_into()?);
+        self.copy(length as usize)?;
+        Ok(())
+    }
+
+    fn payload(&mut self, tag: u8, depth: usize) -> Result<()> {
+        if (tag == NBT_LIST || tag == NBT_COMPOUND) && depth >= self.max_depth {
+            Err(LimitError::NbtTooDeep(self.max_depth))?;
+        }
+        match tag {
+            1 => self.copy(1).map(drop),
+            2 => self.copy(2).map(drop),
+            3 | 5 => self.copy(4).map(drop),
+            4 | 6 => self.copy(8).map(drop),
+            7 => self.array(1),
+            8 => self.string(),
+            NBT_LIST => {
+                let element = self.copy(1)?[0];
+                let length = self.length()?;
+                // Ends take no bytes, so a long list of them would loop
+                // without ever reaching the size limit.
+                if element == NBT_END && length > 0 {
+                    Err(NbtError::InvalidTag(element))?;
+                }
+                for _ in 0..length {
+                    self.payload(element, depth + 1)?;
+                }
+                Ok(())
+            }
+            NBT_COMPOUND => loop {
+                let tag = self.copy(1)?[0];
+                if tag == NBT_END {
+                    return Ok(());
+                }
+                self.string()?;
+                self.payload(tag, depth + 1)?;
+            },
+            11 => self.array(4),
+            12 => self.array(8),
+            tag => Err(NbtError::InvalidTag(tag))?,
+        }
     }
 }
 
@@ -331,7 +670,12 @@ impl MinecraftIo for Slot {
         }
         let id = <VarInt>::minecraft_read(reader)?;
         let count = <i8>::minecraft_read(reader)?;
-        let nbt = nbt::from_reader(reader)?;
+        // A lone TAG_End byte means the item has no NBT.
+        let tag = <u8>::minecraft_read(reader)?;
+        let nbt = match tag {
+            0 => None,
+            _ => Some(nbt::Blob::minecraft_read(&mut [tag].chain(reader))?),
+        };
         Ok(Self::Item { id, count, nbt })
     }
 }
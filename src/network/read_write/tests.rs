@@ -1,4 +1,5 @@
     use super::*;
+    use crate::network::limits::{LimitError, Limits};
     use crate::network::Angle;
 
     #[test]
@@ -92,3 +93,162 @@
         slot.minecraft_write(&mut vec);
         assert_eq!(vec, &[1, 1, 1, 0]);
     }
+
+    #[test]
+    fn test_bit_set() {
+        let set: BitSet = [0, 3, 64].into_iter().collect();
+        assert!(set.get(0) && set.get(3) && set.get(64));
+        assert!(!set.get(1) && !set.get(1000));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 3, 64]);
+
+        let mut vec = vec![];
+        set.minecraft_write(&mut vec).unwrap();
+        assert_eq!(vec, [2, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(BitSet::minecraft_read(&mut &vec[..]).unwrap(), set);
+    }
+
+    #[test]
+    fn test_read_slot() {
+        let mut vec = vec![];
+        let slot = Slot::Item {
+            id: VarInt(1),
+            count: 1,
+            nbt: None,
+        };
+        slot.minecraft_write(&mut vec);
+        assert_eq!(Slot::minecraft_read(&mut &vec[..]).unwrap(), slot);
+
+        vec.clear();
+        let mut blob = nbt::Blob::new();
+        blob.insert("Damage", 3i32).unwrap();
+        let slot = Slot::Item {
+            id: VarInt(700),
+            count: 1,
+            nbt: Some(blob),
+        };
+        slot.minecraft_write(&mut vec);
+        assert_eq!(Slot::minecraft_read(&mut &vec[..]).unwrap(), slot);
+    }
+
+    #[test]
+    fn test_fixed_array() {
+        let mut vec = vec![];
+        [1u16, 2, 3].minecraft_write(&mut vec).unwrap();
+        assert_eq!(vec, &[0, 1, 0, 2, 0, 3]);
+        assert_eq!(
+            <[u16; 3]>::minecraft_read(&mut &vec[..]).unwrap(),
+            [1, 2, 3]
+        );
+        assert!(<[u16; 4]>::minecraft_read(&mut &vec[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_bad_length() {
+        // -1 as a VarInt.
+        let bytes: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x0f];
+        let error = <Vec<u8>>::minecraft_read(&mut &bytes[..]).unwrap_err();
+        assert!(matches!(
+            error.downcast::<ArrayError>().unwrap(),
+            ArrayError::NegativeLength(-1)
+        ));
+
+        // A length far beyond the data fails without allocating it.
+        let bytes: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x07, b'a'];
+        assert!(String::minecraft_read(&mut &bytes[..]).is_err());
+        assert!(LengthPrefixedByteArray::<VarInt>::minecraft_read(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_string_and_array_limits() {
+        let limits = Limits {
+            max_array_length: 2,
+            ..Limits::DEFAULT
+        }
+        .with_max_string_length(3);
+        limits.scope(|| {
+            let mut vec = vec![];
+            "äöü".to_owned().minecraft_write(&mut vec).unwrap();
+            assert_eq!(String::minecraft_read(&mut &vec[..]).unwrap(), "äöü");
+
+            vec.clear();
+            "abcd".to_owned().minecraft_write(&mut vec).unwrap();
+            let error = String::minecraft_read(&mut &vec[..]).unwrap_err();
+            assert_eq!(
+                error.downcast::<LimitError>().unwrap(),
+                LimitError::StringTooLong { length: 4, max: 3 }
+            );
+
+            vec.clear();
+            vec![1u8, 2, 3].minecraft_write(&mut vec).unwrap();
+            let error = <Vec<u8>>::minecraft_read(&mut &vec[..]).unwrap_err();
+            assert_eq!(
+                error.downcast::<LimitError>().unwrap(),
+                LimitError::ArrayTooLong { length: 3, max: 2 }
+            );
+        });
+    }
+
+    #[test]
+    fn test_nbt_limits() {
+        // Compounds nested two deep inside the root compound.
+        let nested: &[u8] = &[10, 0, 0, 10, 0, 1, b'a', 10, 0, 1, b'b', 0, 0, 0];
+        assert!(nbt::Blob::minecraft_read(&mut &nested[..]).is_ok());
+        let limits = Limits {
+            max_nbt_depth: 2,
+            ..Limits::DEFAULT
+        };
+        let error = limits
+            .scope(|| nbt::Blob::minecraft_read(&mut &nested[..]))
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<LimitError>().unwrap(),
+            LimitError::NbtTooDeep(2)
+        );
+
+        // A byte array claiming two gigabytes.
+        let huge: &[u8] = &[10, 0, 0, 7, 0, 1, b'x', 0x7f, 0xff, 0xff, 0xff];
+        let error = nbt::Blob::minecraft_read(&mut &huge[..]).unwrap_err();
+        assert_eq!(
+            error.downcast::<LimitError>().unwrap(),
+            LimitError::NbtTooLarge(Limits::DEFAULT.max_nbt_size)
+        );
+
+        // A list of a billion empty tags.
+        let ends: &[u8] = &[10, 0, 0, 9, 0, 1, b'l', 0, 0x40, 0, 0, 0];
+        let error = nbt::Blob::minecraft_read(&mut &ends[..]).unwrap_err();
+        assert!(matches!(
+            error.downcast::<NbtError>().unwrap(),
+            NbtError::InvalidTag(0)
+        ));
+    }
+
+    #[test]
+    fn test_empty_nbt() {
+        // A lone TAG_End is an empty tag, and is written back as one.
+        let nbt = Nbt::minecraft_read(&mut &[0][..]).unwrap();
+        assert_eq!(nbt, Nbt::default());
+        let mut vec = vec![];
+        nbt.minecraft_write(&mut vec).unwrap();
+        assert_eq!(vec, [0]);
+
+        let mut blob = nbt::Blob::new();
+        blob.insert("a", 1i8).unwrap();
+        let mut vec = vec![];
+        Nbt(blob.clone()).minecraft_write(&mut vec).unwrap();
+        assert_eq!(vec, [10, 0, 0, 1, 0, 1, b'a', 1, 0]);
+        assert_eq!(Nbt::minecraft_read(&mut &vec[..]).unwrap(), Nbt(blob));
+    }
+
+    #[test]
+    fn test_equipment() {
+        let sword = Slot::Item {
+            id: VarInt(702),
+            count: 1,
+            nbt: None,
+        };
+        let equipment = Equipment(vec![(0, sword.clone()), (5, Slot::Nothing)]);
+        let mut vec = vec![];
+        equipment.minecraft_write(&mut vec).unwrap();
+        assert_eq!(vec, &[0x80, 1, 0xbe, 0x05, 1, 0, 5, 0]);
+        assert_eq!(Equipment::minecraft_read(&mut &vec[..]).unwrap(), equipment);
+    }
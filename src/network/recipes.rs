@@ -0,0 +1,621 @@
+use crate::network::packets::DeclareRecipes;
+use crate::network::read_write::check_array_length;
+use crate::network::{Identifier, MinecraftIo, Slot, VarInt};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+/// Recipe types with no data, whose results are worked out by the client.
+const SPECIAL_RECIPE_TYPES: &[&str] = &[
+    "crafting_special_armordye",
+    "crafting_special_bookcloning",
+    "crafting_special_mapcloning",
+    "crafting_special_mapextending",
+    "crafting_special_firework_rocket",
+    "crafting_special_firework_star",
+    "crafting_special_firework_star_fade",
+    "crafting_special_repairitem",
+    "crafting_special_tippedarrow",
+    "crafting_special_bannerduplicate",
+    "crafting_special_banneraddpattern",
+    "crafting_special_shielddecoration",
+    "crafting_special_shulkerboxcoloring",
+    "crafting_special_suspiciousstew",
+];
+
+#[derive(Clone, Debug)]
+pub enum RecipeError {
+    UnknownType(String),
+    /// A shaped recipe's grid has more cells than can be counted.
+    GridTooLarge {
+        width: i32,
+        height: i32,
+    },
+    /// A shaped recipe has a different number of ingredients than cells.
+    GridSizeMismatch {
+        width: i32,
+        height: i32,
+        ingredients: usize,
+    },
+}
+
+impl Display for RecipeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for RecipeError {}
+
+/// Any one of these items fills the ingredient. An empty list is an empty
+/// space in a shaped recipe.
+pub type Ingredient = Vec<Slot>;
+
+/// The recipes made in a furnace, blast furnace, smoker or on a campfire.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CookingRecipe {
+    pub group: String,
+    pub ingredient: Ingredient,
+    pub result: Slot,
+    pub experience: f32,
+    pub cooking_time: VarInt,
+}
+
+impl MinecraftIo for CookingRecipe {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.group.minecraft_write(writer)?;
+        self.ingredient.minecraft_write(writer)?;
+        self.result.minecraft_write(writer)?;
+        self.experience.minecraft_write(writer)?;
+        self.cooking_time.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            group: String::minecraft_read(reader)?,
+            ingredient: Ingredient::minecraft_read(reader)?,
+            result: Slot::minecraft_read(reader)?,
+            experience: f32::minecraft_read(reader)?,
+            cooking_time: VarInt::minecraft_read(reader)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecipeData {
+    Shapeless {
+        group: String,
+        ingredients: Vec<Ingredient>,
+        result: Slot,
+    },
+    /// Ingredients are in rows, `width` to a row.
+    Shaped {
+        width: VarInt,
+        height: VarInt,
+        group: String,
+        ingredients: Vec<Ingredient>,
+        result: Slot,
+    },
+    Smelting(CookingRecipe),
+    Blasting(CookingRecipe),
+    Smoking(CookingRecipe),
+    CampfireCooking(CookingRecipe),
+    Stonecutting {
+        group: String,
+        ingredient: Ingredient,
+        result: Slot,
+    },
+    Smithing {
+        base: Ingredient,
+        addition: Ingredient,
+        result: Slot,
+    },
+    /// One of the `crafting_special_*` types, named without the namespace.
+    Special(String),
+}
+
+impl RecipeData {
+    /// The type identifier, without the `minecraft:` namespace.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Self::Shapeless { .. } => "crafting_shapeless",
+            Self::Shaped { .. } => "crafting_shaped",
+            Self::Smelting(_) => "smelting",
+            Self::Blasting(_) => "blasting",
+            Self::Smoking(_) => "smoking",
+            Self::CampfireCooking(_) => "campfire_cooking",
+            Self::Stonecutting { .. } => "stonecutting",
+            Self::Smithing { .. } => "smithing",
+            Self::Special(name) => name,
+        }
+    }
+
+    fn read(type_name: &str, reader: &mut impl Read) -> Result<Self> {
+        Ok(match type_name {
+            "crafting_shapeless" => Self::Shapeless {
+                group: String::minecraft_read(reader)?,
+                ingredients: <Vec<Ingredient>>::minecraft_read(reader)?,
+                result: Slot::minecraft_read(reader)?,
+            },
+            "crafting_shaped" => {
+                let width = VarInt::minecraft_read(reader)?;
+                let height = VarInt::minecraft_read(reader)?;
+                let group = String::minecraft_read(reader)?;
+                // The grid is not length prefixed; its size is the width
+                // times the height.
+                let cells = (width.0.max(0) as usize)
+                    .checked_mul(height.0.max(0) as usize)
+                    .ok_or(RecipeError::GridTooLarge {
+                        width: width.0,
+                        height: height.0,
+                    })?;
+                let mut ingredients = vec![];
+                for _ in 0..check_array_length(cells)? {
+                    ingredients.push(Ingredient::minecraft_read(reader)?);
+                }
+                Self::Shaped {
+                    width,
+                    height,
+                    group,
+                    ingredients,
+                    result: Slot::minecraft_read(reader)?,
+                }
+            }
+            "smelting" => Self::Smelting(CookingRecipe::minecraft_read(reader)?),
+            "blasting" => Self::Blasting(CookingRecipe::minecraft_read(reader)?),
+            "smoking" => Self::Smoking(CookingRecipe::minecraft_read(reader)?),
+            "campfire_cooking" => Self::CampfireCooking(CookingRecipe::minecraft_read(reader)?),
+            "stonecutting" => Self::Stonecutting {
+                group: String::minecraft_read(reader)?,
+                ingredient: Ingredient::minecraft_read(reader)?,
+                result: Slot::minecraft_read(reader)?,
+            },
+            "smithing" => Self::Smithing {
+                base: Ingredient::minecraft_read(reader)?,
+                addition: Ingredient::minecraft_read(reader)?,
+                result: Slot::minecraft_read(reader)?,
+            },
+            name if SPECIAL_RECIPE_TYPES.contains(&name) => Self::Special(name.to_owned()),
+            // Without knowing its layout, nothing after this recipe can be
+            // read.
+            name => Err(RecipeError::UnknownType(name.to_owned()))?,
+        })
+    }
+
+    fn write(&self, writer: &mut impl Write) -> Result<()> {
+        match self {
+            Self::Shapeless {
+                group,
+                ingredients,
+                result,
+            } => {
+                group.minecraft_write(writer)?;
+                ingredients.minecraft_write(writer)?;
+                result.minecraft_write(writer)
+            }
+            Self::Shaped {
+                width,
+                height,
+                group,
+                ingredients,
+                result,
+            } => {
+                let cells = (width.0.max(0) as usize).checked_mul(height.0.max(0) as usize);
+                if cells != Some(ingredients.len()) {
+                    Err(RecipeError::GridSizeMismatch {
+                        width: width.0,
+                        height: height.0,
+                        ingredients: ingredients.len(),
+                    })?;
+                }
+                width.minecraft_write(writer)?;
+                height.minecraft_write(writer)?;
+                group.minecraft_write(writer)?;
+                for ingredient in ingredients {
+                    ingredient.minecraft_write(writer)?;
+                }
+                result.minecraft_write(writer)
+            }
+            Self::Smelting(recipe)
+            | Self::Blasting(recipe)
+            | Self::Smoking(recipe)
+            | Self::CampfireCooking(recipe) => recipe.minecraft_write(writer),
+            Self::Stonecutting {
+                group,
+                ingredient,
+                result,
+            } => {
+                group.minecraft_write(writer)?;
+                ingredient.minecraft_write(writer)?;
+                result.minecraft_write(writer)
+            }
+            Self::Smithing {
+                base,
+                addition,
+                result,
+            } => {
+                base.minecraft_write(writer)?;
+                addition.minecraft_write(writer)?;
+                result.minecraft_write(writer)
+            }
+            Self::Special(_) => Ok(()),
+        }
+    }
+
+    /// What the recipe makes. Special recipes have no fixed result.
+    pub fn result(&self) -> Option<&Slot> {
+        match self {
+            Self::Shapeless { result, .. }
+            | Self::Shaped { result, .. }
+            | Self::Stonecutting { result, .. }
+            | Self::Smithing { result, .. } => Some(result),
+            Self::Smelting(recipe)
+            | Self::Blasting(recipe)
+            | Self::Smoking(recipe)
+            | Self::CampfireCooking(recipe) => Some(&recipe.result),
+            Self::Special(_) => None,
+        }
+    }
+
+    /// Every ingredient the recipe consumes, leaving out the empty spaces of
+    /// a shaped recipe.
+    pub fn ingredients(&self) -> Vec<&Ingredient> {
+        let ingredients: Vec<&Ingredient> = match self {
+            Self::Shapeless { ingredients, .. } | Self::Shaped { ingredients, .. } => {
+                ingredients.iter().collect()
+            }
+            Self::Smelting(recipe)
+            | Self::Blasting(recipe)
+            | Self::Smoking(recipe)
+            | Self::CampfireCooking(recipe) => vec![&recipe.ingredient],
+            Self::Stonecutting { ingredient, .. } => vec![ingredient],
+            Self::Smithing { base, addition, .. } => vec![base, addition],
+            Self::Special(_) => vec![],
+        };
+        ingredients
+            .into_iter()
+            .filter(|ingredient| !ingredient.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recipe {
+    pub id: Identifier,
+    pub data: RecipeData,
+}
+
+impl MinecraftIo for Recipe {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        Identifier(format!("minecraft:{}", self.data.type_name())).minecraft_write(writer)?;
+        self.id.minecraft_write(writer)?;
+        self.data.write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        let recipe_type = Identifier::minecraft_read(reader)?;
+        let type_name = recipe_type
+            .0
+            .strip_prefix("minecraft:")
+            .unwrap_or(&recipe_type.0);
+        let id = Identifier::minecraft_read(reader)?;
+        let data = RecipeData::read(type_name, reader)?;
+        Ok(Self { id, data })
+    }
+}
+
+/// Item counts by item ID, ignoring NBT.
+fn count_items(inventory: &[Slot]) -> HashMap<i32, i32> {
+    let mut counts = HashMap::new();
+    for slot in inventory {
+        if let Slot::Item { id, count, .. } = slot {
+            *counts.entry(id.0).or_insert(0) += *count as i32;
+        }
+    }
+    counts
+}
+
+/// Whether each ingredient can be given a different item from `counts`,
+/// trying the alternatives of each in turn.
+fn satisfy(ingredients: &[&Ingredient], counts: &mut HashMap<i32, i32>) -> bool {
+    let Some((first, rest)) = ingredients.split_first() else {
+        return true;
+    };
+    for option in first.iter() {
+        let Some(id) = option.item_id() else {
+            continue;
+        };
+        let available = counts.get(&id.0).copied().unwrap_or(0);
+        if available < 1 {
+            continue;
+        }
+        counts.insert(id.0, available - 1);
+        let satisfied = satisfy(rest, counts);
+        counts.insert(id.0, available);
+        if satisfied {
+            return true;
+        }
+    }
+    false
+}
+
+/// The recipes a server has declared, for crafting helpers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecipeBook {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeBook {
+    pub fn new(recipes: Vec<Recipe>) -> Self {
+        Self { recipes }
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.id == id)
+    }
+
+    /// The recipes whose ingredients can all be taken from `inventory` at
+    /// once. Special recipes are left out, since what they need depends on
+    /// NBT.
+    pub fn craftable(&self, inventory: &[Slot]) -> Vec<&Recipe> {
+        let mut counts = count_items(inventory);
+        self.recipes
+            .iter()
+            .filter(|recipe| !matches!(recipe.data, RecipeData::Special(_)))
+            .filter(|recipe| satisfy(&recipe.data.ingredients(), &mut counts))
+            .collect()
+    }
+
+    /// The recipes that make an item.
+    pub fn recipes_for(&self, item_id: VarInt) -> Vec<&Recipe> {
+        self.recipes
+            .iter()
+            .filter(|recipe| {
+                recipe
+                    .data
+                    .result()
+                    .and_then(Slot::item_id)
+                    .is_some_and(|id| id == item_id)
+            })
+            .collect()
+    }
+}
+
+impl DeclareRecipes {
+    pub fn recipe_book(&self) -> RecipeBook {
+        RecipeBook::new(self.recipes.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::limits::LimitError;
+    use crate::network::{Direction, Packet, State};
+
+    const OAK_LOG: i32 = 110;
+    const OAK_PLANKS: i32 = 23;
+    const BIRCH_PLANKS: i32 = 24;
+    const STICK: i32 = 670;
+    const CRAFTING_TABLE: i32 = 259;
+    const IRON_ORE: i32 = 42;
+    const IRON_INGOT: i32 = 692;
+
+    fn item(id: i32, count: i8) -> Slot {
+        Slot::Item {
+            id: VarInt(id),
+            count,
+            nbt: None,
+        }
+    }
+
+    fn one_of(ids: &[i32]) -> Ingredient {
+        ids.iter().map(|&id| item(id, 1)).collect()
+    }
+
+    fn recipes() -> Vec<Recipe> {
+        let planks = one_of(&[OAK_PLANKS, BIRCH_PLANKS]);
+        vec![
+            Recipe {
+                id: Identifier("minecraft:oak_planks".to_owned()),
+                data: RecipeData::Shapeless {
+                    group: "planks".to_owned(),
+                    ingredients: vec![one_of(&[OAK_LOG])],
+                    result: item(OAK_PLANKS, 4),
+                },
+            },
+            Recipe {
+                id: Identifier("minecraft:stick".to_owned()),
+                data: RecipeData::Shaped {
+                    width: VarInt(1),
+                    height: VarInt(2),
+                    group: "sticks".to_owned(),
+                    ingredients: vec![planks.clone(), planks.clone()],
+                    result: item(STICK, 4),
+                },
+            },
+            Recipe {
+                id: Identifier("minecraft:crafting_table".to_owned()),
+                data: RecipeData::Shaped {
+                    width: VarInt(2),
+                    height: VarInt(2),
+                    group: String::new(),
+                    ingredients: vec![planks.clone(), planks.clone(), planks.clone(), planks],
+                    result: item(CRAFTING_TABLE, 1),
+                },
+            },
+            Recipe {
+                id: Identifier("minecraft:iron_ingot".to_owned()),
+                data: RecipeData::Smelting(CookingRecipe {
+                    group: "iron_ingot".to_owned(),
+                    ingredient: one_of(&[IRON_ORE]),
+                    result: item(IRON_INGOT, 1),
+                    experience: 0.7,
+                    cooking_time: VarInt(200),
+                }),
+            },
+            Recipe {
+                id: Identifier("minecraft:iron_ingot_from_blasting".to_owned()),
+                data: RecipeData::Blasting(CookingRecipe {
+                    group: "iron_ingot".to_owned(),
+                    ingredient: one_of(&[IRON_ORE]),
+                    result: item(IRON_INGOT, 1),
+                    experience: 0.7,
+                    cooking_time: VarInt(100),
+                }),
+            },
+            Recipe {
+                id: Identifier("minecraft:armor_dye".to_owned()),
+                data: RecipeData::Special("crafting_special_armordye".to_owned()),
+            },
+        ]
+    }
+
+    fn ids(recipes: Vec<&Recipe>) -> Vec<&str> {
+        recipes
+            .into_iter()
+            .map(|recipe| recipe.id.0.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_declare_recipes_round_trip() {
+        let mut recipes = recipes();
+        recipes.push(Recipe {
+            id: Identifier("minecraft:stone_slab_from_stonecutting".to_owned()),
+            data: RecipeData::Stonecutting {
+                group: String::new(),
+                ingredient: one_of(&[1]),
+                result: item(2, 2),
+            },
+        });
+        recipes.push(Recipe {
+            id: Identifier("minecraft:netherite_sword_smithing".to_owned()),
+            data: RecipeData::Smithing {
+                base: one_of(&[3]),
+                addition: one_of(&[4]),
+                result: item(5, 1),
+            },
+        });
+        let packet = Packet::DeclareRecipes(DeclareRecipes {
+            recipes: recipes.clone(),
+        });
+        let mut payload = vec![];
+        packet.write_payload(&mut payload).unwrap();
+
+        let Packet::DeclareRecipes(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected DeclareRecipes");
+        };
+        assert_eq!(read.recipes, recipes);
+    }
+
+    #[test]
+    fn test_read_shaped_recipe() {
+        // A 2×1 grid with an empty space, from a server that leaves out the
+        // namespace of the type.
+        let mut bytes = vec![];
+        Identifier("crafting_shaped".to_owned())
+            .minecraft_write(&mut bytes)
+            .unwrap();
+        Identifier("test:thing".to_owned())
+            .minecraft_write(&mut bytes)
+            .unwrap();
+        bytes.extend([2, 1, 0]);
+        one_of(&[STICK]).minecraft_write(&mut bytes).unwrap();
+        one_of(&[]).minecraft_write(&mut bytes).unwrap();
+        item(7, 1).minecraft_write(&mut bytes).unwrap();
+
+        let recipe = Recipe::minecraft_read(&mut &bytes[..]).unwrap();
+        assert_eq!(recipe.id, "test:thing");
+        assert_eq!(recipe.data.ingredients(), [&one_of(&[STICK])]);
+        assert_eq!(recipe.data.result(), Some(&item(7, 1)));
+
+        let mut unknown = vec![];
+        Identifier("mod:machine".to_owned())
+            .minecraft_write(&mut unknown)
+            .unwrap();
+        Identifier("mod:thing".to_owned())
+            .minecraft_write(&mut unknown)
+            .unwrap();
+        assert!(Recipe::minecraft_read(&mut &unknown[..]).is_err());
+    }
+
+    #[test]
+    fn test_shaped_grid_size() {
+        // A 65536×65536 grid is refused before anything is read for it.
+        let mut bytes = vec![];
+        Identifier("minecraft:crafting_shaped".to_owned())
+            .minecraft_write(&mut bytes)
+            .unwrap();
+        Identifier("test:huge".to_owned())
+            .minecraft_write(&mut bytes)
+            .unwrap();
+        VarInt(65536).minecraft_write(&mut bytes).unwrap();
+        VarInt(65536).minecraft_write(&mut bytes).unwrap();
+        String::new().minecraft_write(&mut bytes).unwrap();
+        let error = Recipe::minecraft_read(&mut &bytes[..]).unwrap_err();
+        assert!(error.is::<LimitError>());
+
+        let recipe = Recipe {
+            id: Identifier("test:short".to_owned()),
+            data: RecipeData::Shaped {
+                width: VarInt(2),
+                height: VarInt(2),
+                group: String::new(),
+                ingredients: vec![one_of(&[STICK])],
+                result: item(STICK, 1),
+            },
+        };
+        let error = recipe.minecraft_write(&mut vec![]).unwrap_err();
+        assert!(matches!(
+            error.downcast::<RecipeError>().unwrap(),
+            RecipeError::GridSizeMismatch { ingredients: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_craftable() {
+        let book = RecipeBook::new(recipes());
+
+        assert!(book.craftable(&[]).is_empty());
+        assert_eq!(
+            ids(book.craftable(&[item(OAK_LOG, 1), Slot::Nothing])),
+            ["minecraft:oak_planks"]
+        );
+
+        // Two planks make sticks but not a crafting table; mixed planks
+        // count together.
+        let inventory = [item(OAK_PLANKS, 1), item(BIRCH_PLANKS, 1)];
+        assert_eq!(ids(book.craftable(&inventory)), ["minecraft:stick"]);
+        let inventory = [item(OAK_PLANKS, 3), item(BIRCH_PLANKS, 1)];
+        assert_eq!(
+            ids(book.craftable(&inventory)),
+            ["minecraft:stick", "minecraft:crafting_table"]
+        );
+
+        assert_eq!(
+            ids(book.craftable(&[item(IRON_ORE, 1)])),
+            ["minecraft:iron_ingot", "minecraft:iron_ingot_from_blasting"]
+        );
+    }
+
+    #[test]
+    fn test_recipes_for() {
+        let book = RecipeBook::new(recipes());
+        assert_eq!(
+            ids(book.recipes_for(VarInt(IRON_INGOT))),
+            ["minecraft:iron_ingot", "minecraft:iron_ingot_from_blasting"]
+        );
+        assert_eq!(ids(book.recipes_for(VarInt(STICK))), ["minecraft:stick"]);
+        assert!(book.recipes_for(VarInt(1)).is_empty());
+        assert!(book.get("minecraft:armor_dye").is_some());
+    }
+}
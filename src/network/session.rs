@@ -0,0 +1,141 @@
+use anyhow::Result;
+use serde::Serialize;
+use uuid::Uuid;
+
+pub const MOJANG_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+/// The player a client logs in as. `access_token` is only needed to join
+/// servers in online mode.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    pub username: String,
+    pub uuid: Uuid,
+    pub access_token: Option<String>,
+}
+
+impl Account {
+    pub fn offline(username: &str) -> Self {
+        Self {
+            username: username.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The session server call a client makes before answering an
+/// `EncryptionRequest`, so the server can verify who is logging in.
+pub trait SessionService {
+    fn join(&self, access_token: &str, profile: Uuid, server_hash: &str) -> Result<()>;
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JoinRequest<'a> {
+    access_token: &'a str,
+    selected_profile: String,
+    server_id: &'a str,
+}
+
+/// A session server reached over HTTP, Mojang's unless another base URL is
+/// given.
+#[derive(Clone, Debug)]
+pub struct HttpSessionService {
+    base_url: String,
+}
+
+impl HttpSessionService {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+}
+
+impl Default for HttpSessionService {
+    fn default() -> Self {
+        Self::new(MOJANG_SESSION_SERVER)
+    }
+}
+
+impl SessionService for HttpSessionService {
+    fn join(&self, access_token: &str, profile: Uuid, server_hash: &str) -> Result<()> {
+        let url = format!("{}/session/minecraft/join", self.base_url);
+        ureq::post(&url).send_json(JoinRequest {
+            access_token,
+            selected_profile: profile.simple().to_string(),
+            server_id: server_hash,
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Accepts one HTTP request, answers it with `status` and returns the
+    /// request line and body.
+    fn stand_in_server(status: &'static str) -> (String, thread::JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = stream;
+            write!(stream, "HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n").unwrap();
+            (request_line, String::from_utf8(body).unwrap())
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_join() {
+        let (url, server) = stand_in_server("204 No Content");
+        let profile = Uuid::from_u128(0x0123456789abcdef0123456789abcdef);
+        HttpSessionService::new(&url)
+            .join("token", profile, "-7c9d5b")
+            .unwrap();
+
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /session/minecraft/join "));
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "accessToken": "token",
+                "selectedProfile": "0123456789abcdef0123456789abcdef",
+                "serverId": "-7c9d5b",
+            })
+        );
+    }
+
+    #[test]
+    fn test_join_rejected() {
+        let (url, server) = stand_in_server("403 Forbidden");
+        let result = HttpSessionService::new(&url).join("token", Uuid::nil(), "0");
+        assert!(result.is_err());
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use crate::network::connection::Connection;
+use crate::network::legacy_ping::legacy_ping;
+use crate::network::{packets, Chat, Direction, Packet, State};
+use anyhow::Result;
+use base64::Engine;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+const FAVICON_PREFIX: &str = "data:image/png;base64,";
+
+#[derive(Copy, Clone, Debug)]
+pub enum StatusError {
+    UnexpectedPacket(i32),
+    PayloadMismatch { sent: i64, received: i64 },
+    InvalidFavicon,
+}
+
+impl Display for StatusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for StatusError {}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Version {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Players {
+    pub max: i32,
+    pub online: i32,
+    #[serde(default)]
+    pub sample: Vec<PlayerSample>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct PlayerSample {
+    pub name: String,
+    pub id: Uuid,
+}
+
+/// The JSON document in `StatusResponse`, before the description and favicon
+/// are turned into their typed forms.
+#[derive(Deserialize)]
+struct StatusJson {
+    version: Version,
+    players: Players,
+    #[serde(default)]
+    description: serde_json::Value,
+    favicon: Option<String>,
+}
+
+/// What a server reports about itself in the server list.
+#[derive(Clone, Debug)]
+pub struct ServerStatus {
+    pub version: Version,
+    pub players: Players,
+    pub description: Chat,
+    pub favicon: Option<image::DynamicImage>,
+    pub latency: Duration,
+}
+
+impl ServerStatus {
+    /// Parses the `StatusResponse` JSON. The latency is left at zero.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let status: StatusJson = serde_json::from_str(json)?;
+        let favicon = status.favicon.as_deref().map(decode_favicon).transpose()?;
+        Ok(Self {
+            version: status.version,
+            players: status.players,
+            description: Chat(status.description.to_string()),
+            favicon,
+            latency: Duration::ZERO,
+        })
+    }
+}
+
+fn decode_favicon(favicon: &str) -> Result<image::DynamicImage> {
+    let data = favicon
+        .strip_prefix(FAVICON_PREFIX)
+        .ok_or(StatusError::InvalidFavicon)?;
+    // Some servers wrap the base64 text like a MIME body.
+    let data: String = data.split_whitespace().collect();
+    let png = base64::engine::general_purpose::STANDARD.decode(data)?;
+    Ok(image::load_from_memory_with_format(
+        &png,
+        image::ImageFormat::Png,
+    )?)
+}
+
+/// Asks a server for its status the way the server list does, and measures
+/// the round trip time of the ping that follows. Servers that do not answer
+/// the status handshake are asked again with the legacy ping.
+pub fn ping(address: &str, port: u16) -> Result<ServerStatus> {
+    modern_ping(address, port).or_else(|error| {
+        legacy_ping(address, port).map_err(|legacy_error| {
+            error.context(format!("legacy ping also failed: {legacy_error}"))
+        })
+    })
+}
+
+/// Pings using only the status handshake, without the legacy fallback.
+pub fn modern_ping(address: &str, port: u16) -> Result<ServerStatus> {
+    let stream = open_stream(address, port)?;
+    let mut connection = Connection::from_stream(stream, Direction::ClientBound)?;
+    query_status(&mut connection, address, port)
+}
+
+pub(crate) fn open_stream(address: &str, port: u16) -> Result<TcpStream> {
+    let socket_address = (address, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("{address} did not resolve"))?;
+    let stream = TcpStream::connect_timeout(&socket_address, PING_TIMEOUT)?;
+    stream.set_read_timeout(Some(PING_TIMEOUT))?;
+    stream.set_write_timeout(Some(PING_TIMEOUT))?;
+    Ok(stream)
+}
+
+/// Runs the status exchange on a freshly opened connection.
+pub fn query_status(connection: &mut Connection, address: &str, port: u16) -> Result<ServerStatus> {
+    connection.handshake(address, port, State::Status)?;
+    connection.write_packet(&Packet::StatusRequest(packets::StatusRequest {}))?;
+    let json = match connection.read_packet()? {
+        Packet::StatusResponse(response) => response.json_data,
+        packet => Err(StatusError::UnexpectedPacket(packet.id().2 .0))?,
+    };
+
+    let payload = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    let start = Instant::now();
+    connection.write_packet(&Packet::StatusPing(packets::StatusPing { payload }))?;
+    match connection.read_packet()? {
+        Packet::StatusPong(pong) if pong.payload == payload => {}
+        Packet::StatusPong(pong) => Err(StatusError::PayloadMismatch {
+            sent: payload,
+            received: pong.payload,
+        })?,
+        packet => Err(StatusError::UnexpectedPacket(packet.id().2 .0))?,
+    }
+    let latency = start.elapsed();
+
+    let mut status = ServerStatus::from_json(&json)?;
+    status.latency = latency;
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn favicon() -> String {
+        let icon = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 255]));
+        let mut png = Cursor::new(vec![]);
+        image::DynamicImage::ImageRgba8(icon)
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        let data = base64::engine::general_purpose::STANDARD.encode(png.into_inner());
+        format!("{FAVICON_PREFIX}{data}")
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let json = serde_json::json!({
+            "version": { "name": "1.18.2", "protocol": 758 },
+            "players": {
+                "max": 100,
+                "online": 1,
+                "sample": [{ "name": "thinkofdeath", "id": "4566e69f-c907-48ee-8d71-d7ba5aa00d20" }]
+            },
+            "description": { "text": "Hello world" },
+            "favicon": favicon(),
+        });
+        let status = ServerStatus::from_json(&json.to_string()).unwrap();
+        assert_eq!(status.version.name, "1.18.2");
+        assert_eq!(status.version.protocol, 758);
+        assert_eq!(status.players.max, 100);
+        assert_eq!(status.players.online, 1);
+        assert_eq!(status.players.sample[0].name, "thinkofdeath");
+        assert_eq!(
+            status.players.sample[0].id,
+            Uuid::parse_str("4566e69f-c907-48ee-8d71-d7ba5aa00d20").unwrap()
+        );
+        assert_eq!(status.description, r#"{"text":"Hello world"}"#);
+        let favicon = status.favicon.unwrap();
+        assert_eq!((favicon.width(), favicon.height()), (4, 4));
+    }
+
+    #[test]
+    fn test_parse_minimal_status() {
+        let json = r#"{
+            "version": { "name": "Paper 1.18.2", "protocol": 758 },
+            "players": { "max": 20, "online": 0 },
+            "description": "A Minecraft Server"
+        }"#;
+        let status = ServerStatus::from_json(json).unwrap();
+        assert!(status.players.sample.is_empty());
+        assert_eq!(status.description, r#""A Minecraft Server""#);
+        assert!(status.favicon.is_none());
+    }
+
+    #[test]
+    fn test_invalid_favicon() {
+        let json = r#"{
+            "version": { "name": "1.18.2", "protocol": 758 },
+            "players": { "max": 20, "online": 0 },
+            "favicon": "http://example.com/icon.png"
+        }"#;
+        assert!(ServerStatus::from_json(json).is_err());
+    }
+
+    #[test]
+    fn test_ping() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut server = Connection::from_stream(stream, Direction::ServerBound).unwrap();
+            assert!(matches!(
+                server.read_packet().unwrap(),
+                Packet::Handshake(_)
+            ));
+            assert_eq!(server.state(), State::Status);
+            assert!(matches!(
+                server.read_packet().unwrap(),
+                Packet::StatusRequest(_)
+            ));
+            server
+                .write_packet(&Packet::StatusResponse(packets::StatusResponse {
+                    json_data: r#"{"version":{"name":"1.18.2","protocol":758},"players":{"max":5,"online":2},"description":{"text":"test"}}"#.to_owned(),
+                }))
+                .unwrap();
+            let Packet::StatusPing(ping) = server.read_packet().unwrap() else {
+                panic!("expected StatusPing");
+            };
+            thread::sleep(Duration::from_millis(20));
+            server
+                .write_packet(&Packet::StatusPong(packets::StatusPong {
+                    payload: ping.payload,
+                }))
+                .unwrap();
+        });
+
+        let status = ping("127.0.0.1", port).unwrap();
+        assert_eq!(status.players.online, 2);
+        assert_eq!(status.description, r#"{"text":"test"}"#);
+        assert!(status.latency >= Duration::from_millis(20));
+        server.join().unwrap();
+    }
+}
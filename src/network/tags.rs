@@ -0,0 +1,333 @@
+use crate::network::packets::Tags;
+use crate::network::{Identifier, MinecraftIo, Nbt, VarInt};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+const BLOCK_REGISTRY: &str = "minecraft:block";
+
+/// The registries in `JoinGame`'s dimension codec.
+const CODEC_REGISTRIES: &[&str] = &["minecraft:dimension_type", "minecraft:worldgen/biome"];
+
+/// A named group of registry entries, like `minecraft:mineable/pickaxe`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tag {
+    pub name: Identifier,
+    pub entries: Vec<VarInt>,
+}
+
+impl MinecraftIo for Tag {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.name.minecraft_write(writer)?;
+        self.entries.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            name: Identifier::minecraft_read(reader)?,
+            entries: <Vec<VarInt>>::minecraft_read(reader)?,
+        })
+    }
+}
+
+/// The tags of one registry, such as `minecraft:block` or `minecraft:item`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegistryTags {
+    pub registry: Identifier,
+    pub tags: Vec<Tag>,
+}
+
+impl MinecraftIo for RegistryTags {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.registry.minecraft_write(writer)?;
+        self.tags.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            registry: Identifier::minecraft_read(reader)?,
+            tags: <Vec<Tag>>::minecraft_read(reader)?,
+        })
+    }
+}
+
+/// Tags by registry, and the names of registry entries where they are
+/// known.
+///
+/// The server sends only IDs: block tags hold block IDs, not the block
+/// states chunks are made of, and neither the names of blocks and items nor
+/// the block each state belongs to are part of the protocol. Those come from
+/// the game's data reports and are supplied with `register_names` and
+/// `register_block_states`; until then states cannot be looked up and IDs
+/// do not resolve to names.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagRegistry {
+    tags: HashMap<String, HashMap<String, Vec<i32>>>,
+    names: HashMap<String, HashMap<i32, Identifier>>,
+    /// The first state ID of each block, indexed by block ID.
+    first_block_states: Vec<i32>,
+    /// The number of block states, one past the last state ID.
+    block_state_count: i32,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the tags of every registry in the packet. A server sends all
+    /// tags again whenever they are reloaded.
+    pub fn load(&mut self, tags: &[RegistryTags]) {
+        for registry in tags {
+            let registry_tags = registry
+                .tags
+                .iter()
+                .map(|tag| {
+                    let entries = tag.entries.iter().map(|entry| entry.0).collect();
+                    (tag.name.0.clone(), entries)
+                })
+                .collect();
+            self.tags.insert(registry.registry.0.clone(), registry_tags);
+        }
+    }
+
+    /// The IDs in a tag, if the registry has that tag.
+    pub fn tag(&self, registry: &str, tag: &str) -> Option<&[i32]> {
+        Some(self.tags.get(registry)?.get(tag)?.as_slice())
+    }
+
+    pub fn contains(&self, registry: &str, tag: &str, id: i32) -> bool {
+        self.tag(registry, tag)
+            .is_some_and(|entries| entries.contains(&id))
+    }
+
+    /// Records which block each block state belongs to, given the first state
+    /// ID of every block in block ID order and the number of block states.
+    /// Each block's states follow on from its first, up to the next block's
+    /// first.
+    pub fn register_block_states(
+        &mut self,
+        first_states: impl IntoIterator<Item = i32>,
+        state_count: i32,
+    ) {
+        self.first_block_states = first_states.into_iter().collect();
+        self.block_state_count = state_count;
+    }
+
+    /// The block a block state belongs to, once block states are registered.
+    pub fn block_of_state(&self, state: i32) -> Option<i32> {
+        if state < 0 || state >= self.block_state_count {
+            return None;
+        }
+        let block = self
+            .first_block_states
+            .partition_point(|&first| first <= state)
+            .checked_sub(1)?;
+        Some(block as i32)
+    }
+
+    /// Whether the block of a block state is in a `minecraft:block` tag. False
+    /// for any state before block states are registered.
+    pub fn contains_block_state(&self, tag: &str, state: i32) -> bool {
+        self.block_of_state(state)
+            .is_some_and(|block| self.contains(BLOCK_REGISTRY, tag, block))
+    }
+
+    /// The names of the tags in a registry that include `id`, sorted.
+    pub fn tags_of(&self, registry: &str, id: i32) -> Vec<Identifier> {
+        let mut names: Vec<Identifier> = self
+            .tags
+            .get(registry)
+            .into_iter()
+            .flatten()
+            .filter(|(_, entries)| entries.contains(&id))
+            .map(|(name, _)| Identifier(name.clone()))
+            .collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        names
+    }
+
+    /// Records the names of a registry's entries, so tag members can be
+    /// resolved to them.
+    pub fn register_names(
+        &mut self,
+        registry: &str,
+        names: impl IntoIterator<Item = (i32, Identifier)>,
+    ) {
+        self.names
+            .entry(registry.to_owned())
+            .or_default()
+            .extend(names);
+    }
+
+    /// Records the registries a server sends in `JoinGame`'s dimension codec,
+    /// such as `minecraft:worldgen/biome`.
+    pub fn register_codec_names(&mut self, codec: &Nbt) {
+        for &registry in CODEC_REGISTRIES {
+            let Some(nbt::Value::Compound(registry_compound)) = codec.0.get(registry) else {
+                continue;
+            };
+            let Some(nbt::Value::List(entries)) = registry_compound.get("value") else {
+                continue;
+            };
+            let names = entries.iter().filter_map(|entry| match entry {
+                nbt::Value::Compound(entry) => match (entry.get("id"), entry.get("name")) {
+                    (Some(nbt::Value::Int(id)), Some(nbt::Value::String(name))) => {
+                        Some((*id, Identifier(name.clone())))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            });
+            self.register_names(registry, names);
+        }
+    }
+
+    pub fn name_of(&self, registry: &str, id: i32) -> Option<&Identifier> {
+        self.names.get(registry)?.get(&id)
+    }
+
+    /// The names of a tag's members, skipping any without a known name.
+    pub fn resolve(&self, registry: &str, tag: &str) -> Vec<&Identifier> {
+        self.tag(registry, tag)
+            .into_iter()
+            .flatten()
+            .filter_map(|&id| self.name_of(registry, id))
+            .collect()
+    }
+}
+
+impl Tags {
+    pub fn registry(&self) -> TagRegistry {
+        let mut registry = TagRegistry::new();
+        registry.load(&self.tags);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Direction, Packet, State};
+
+    fn tag(name: &str, entries: &[i32]) -> Tag {
+        Tag {
+            name: Identifier(name.to_owned()),
+            entries: entries.iter().copied().map(VarInt).collect(),
+        }
+    }
+
+    fn packet() -> Tags {
+        Tags {
+            tags: vec![
+                RegistryTags {
+                    registry: Identifier("minecraft:block".to_owned()),
+                    tags: vec![
+                        tag("minecraft:mineable/pickaxe", &[1, 2, 3]),
+                        tag("minecraft:base_stone_overworld", &[1]),
+                    ],
+                },
+                RegistryTags {
+                    registry: Identifier("minecraft:item".to_owned()),
+                    tags: vec![tag("minecraft:planks", &[23, 24])],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_tags_round_trip() {
+        let mut payload = vec![];
+        Packet::Tags(packet()).write_payload(&mut payload).unwrap();
+        let Packet::Tags(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected Tags");
+        };
+        assert_eq!(read.tags, packet().tags);
+    }
+
+    #[test]
+    fn test_membership() {
+        let registry = packet().registry();
+        assert!(registry.contains("minecraft:block", "minecraft:mineable/pickaxe", 2));
+        assert!(!registry.contains("minecraft:block", "minecraft:mineable/pickaxe", 4));
+        assert!(!registry.contains("minecraft:item", "minecraft:mineable/pickaxe", 2));
+        assert_eq!(
+            registry.tag("minecraft:item", "minecraft:planks"),
+            Some(&[23, 24][..])
+        );
+        assert_eq!(
+            registry.tags_of("minecraft:block", 1),
+            [
+                Identifier("minecraft:base_stone_overworld".to_owned()),
+                Identifier("minecraft:mineable/pickaxe".to_owned()),
+            ]
+        );
+
+        // A reload replaces a registry's tags.
+        let mut registry = registry;
+        registry.load(&[RegistryTags {
+            registry: Identifier("minecraft:block".to_owned()),
+            tags: vec![tag("minecraft:mineable/pickaxe", &[9])],
+        }]);
+        assert!(registry.tags_of("minecraft:block", 1).is_empty());
+        assert!(registry.contains("minecraft:item", "minecraft:planks", 23));
+    }
+
+    #[test]
+    fn test_block_states() {
+        // Blocks 0, 1, 2 and 3 with 1, 1, 4 and 2 states.
+        let mut registry = packet().registry();
+        assert!(!registry.contains_block_state("minecraft:mineable/pickaxe", 2));
+        registry.register_block_states([0, 1, 2, 6], 8);
+        assert_eq!(registry.block_of_state(1), Some(1));
+        assert_eq!(registry.block_of_state(5), Some(2));
+        assert_eq!(registry.block_of_state(7), Some(3));
+        assert_eq!(registry.block_of_state(-1), None);
+        assert_eq!(registry.block_of_state(8), None);
+        assert!(registry.contains_block_state("minecraft:mineable/pickaxe", 5));
+        assert!(registry.contains_block_state("minecraft:base_stone_overworld", 1));
+        assert!(!registry.contains_block_state("minecraft:base_stone_overworld", 2));
+        assert!(!registry.contains_block_state("minecraft:mineable/pickaxe", 0));
+        assert!(!registry.contains_block_state("minecraft:mineable/pickaxe", 8));
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let mut registry = packet().registry();
+        registry.register_names(
+            "minecraft:item",
+            [(23, Identifier("minecraft:oak_planks".to_owned()))],
+        );
+        assert_eq!(
+            registry.resolve("minecraft:item", "minecraft:planks"),
+            [&Identifier("minecraft:oak_planks".to_owned())]
+        );
+
+        let mut plains = HashMap::new();
+        plains.insert(
+            "name".to_owned(),
+            nbt::Value::String("minecraft:plains".to_owned()),
+        );
+        plains.insert("id".to_owned(), nbt::Value::Int(1));
+        let mut biomes = HashMap::new();
+        biomes.insert(
+            "type".to_owned(),
+            nbt::Value::String("minecraft:worldgen/biome".to_owned()),
+        );
+        biomes.insert(
+            "value".to_owned(),
+            nbt::Value::List(vec![nbt::Value::Compound(plains)]),
+        );
+        let mut codec = nbt::Blob::new();
+        codec
+            .insert("minecraft:worldgen/biome", nbt::Value::Compound(biomes))
+            .unwrap();
+        registry.register_codec_names(&Nbt(codec));
+        assert_eq!(
+            registry.name_of("minecraft:worldgen/biome", 1),
+            Some(&Identifier("minecraft:plains".to_owned()))
+        );
+    }
+}
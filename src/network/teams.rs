@@ -0,0 +1,204 @@
+use crate::network::enums::TeamColor;
+use crate::network::packets::Teams;
+use crate::network::{Chat, VarInt};
+use std::collections::HashMap;
+
+pub const MODE_CREATE: i8 = 0;
+pub const MODE_REMOVE: i8 = 1;
+pub const MODE_UPDATE: i8 = 2;
+pub const MODE_ADD_ENTITIES: i8 = 3;
+pub const MODE_REMOVE_ENTITIES: i8 = 4;
+
+pub const FRIENDLY_FIRE: i8 = 0x01;
+pub const SEE_INVISIBLE_TEAMMATES: i8 = 0x02;
+
+/// A scoreboard team. Entities are players by name and other entities by
+/// UUID.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Team {
+    pub display_name: Chat,
+    pub friendly_flags: i8,
+    pub name_tag_visibility: String,
+    pub collision_rule: String,
+    pub color: TeamColor,
+    pub prefix: Chat,
+    pub suffix: Chat,
+    pub entities: Vec<String>,
+}
+
+impl Team {
+    fn update(&mut self, packet: &Teams) {
+        let Teams {
+            display_name: Some(display_name),
+            friendly_flags: Some(friendly_flags),
+            name_tag_visibility: Some(name_tag_visibility),
+            collision_rule: Some(collision_rule),
+            team_color: Some(color),
+            prefix: Some(prefix),
+            suffix: Some(suffix),
+            ..
+        } = packet
+        else {
+            return;
+        };
+        self.display_name = display_name.clone();
+        self.friendly_flags = *friendly_flags;
+        self.name_tag_visibility = name_tag_visibility.clone();
+        self.collision_rule = collision_rule.clone();
+        self.color = *color;
+        self.prefix = prefix.clone();
+        self.suffix = suffix.clone();
+    }
+}
+
+/// The teams a server has created, kept up to date from `Teams` packets.
+#[derive(Clone, Debug, Default)]
+pub struct TeamRegistry {
+    teams: HashMap<String, Team>,
+}
+
+impl TeamRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.teams.get(name)
+    }
+
+    /// The name and team an entity belongs to. An entity is on at most one
+    /// team.
+    pub fn team_of(&self, entity: &str) -> Option<(&str, &Team)> {
+        self.teams
+            .iter()
+            .find(|(_, team)| team.entities.iter().any(|e| e == entity))
+            .map(|(name, team)| (name.as_str(), team))
+    }
+
+    /// Changes to teams that were never created are ignored.
+    pub fn apply(&mut self, packet: &Teams) {
+        let entities = packet.entities.as_deref().unwrap_or_default();
+        match packet.mode {
+            MODE_CREATE => {
+                // Joining a team leaves any other.
+                for team in self.teams.values_mut() {
+                    team.entities.retain(|e| !entities.contains(e));
+                }
+                let mut team = Team {
+                    entities: entities.to_vec(),
+                    ..Team::default()
+                };
+                team.update(packet);
+                self.teams.insert(packet.team_name.clone(), team);
+            }
+            MODE_REMOVE => {
+                self.teams.remove(&packet.team_name);
+            }
+            MODE_UPDATE => {
+                if let Some(team) = self.teams.get_mut(&packet.team_name) {
+                    team.update(packet);
+                }
+            }
+            MODE_ADD_ENTITIES => {
+                if !self.teams.contains_key(&packet.team_name) {
+                    return;
+                }
+                for (name, team) in self.teams.iter_mut() {
+                    team.entities.retain(|e| !entities.contains(e));
+                    if name == &packet.team_name {
+                        team.entities.extend_from_slice(entities);
+                    }
+                }
+            }
+            MODE_REMOVE_ENTITIES => {
+                if let Some(team) = self.teams.get_mut(&packet.team_name) {
+                    team.entities.retain(|e| !entities.contains(e));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Direction, Packet, State};
+
+    fn round_trip(packet: Teams) -> Teams {
+        let mut payload = vec![];
+        Packet::Teams(packet).write_payload(&mut payload).unwrap();
+        let Packet::Teams(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected Teams");
+        };
+        read
+    }
+
+    fn info(mode: i8, prefix: &str) -> Teams {
+        Teams {
+            team_name: "red".to_owned(),
+            mode,
+            display_name: Some(Chat("\"Red\"".to_owned())),
+            friendly_flags: Some(FRIENDLY_FIRE),
+            name_tag_visibility: Some("always".to_owned()),
+            collision_rule: Some("never".to_owned()),
+            team_color: Some(TeamColor::Red),
+            prefix: Some(Chat(prefix.to_owned())),
+            suffix: Some(Chat("\"\"".to_owned())),
+            entities: None,
+        }
+    }
+
+    fn entities(mode: i8, team_name: &str, entities: &[&str]) -> Teams {
+        Teams {
+            team_name: team_name.to_owned(),
+            mode,
+            entities: Some(entities.iter().map(|e| e.to_string()).collect()),
+            ..Teams::default()
+        }
+    }
+
+    #[test]
+    fn test_teams() {
+        let mut registry = TeamRegistry::new();
+        let create = Teams {
+            entities: Some(vec!["Notch".to_owned()]),
+            ..info(MODE_CREATE, "\"[R] \"")
+        };
+        let read = round_trip(create);
+        assert_eq!(read.prefix, Some(Chat("\"[R] \"".to_owned())));
+        assert_eq!(read.entities, Some(vec!["Notch".to_owned()]));
+        registry.apply(&read);
+
+        let update = round_trip(info(MODE_UPDATE, "\"[Red] \""));
+        assert_eq!(update.entities, None);
+        registry.apply(&update);
+        registry.apply(&round_trip(Teams {
+            team_name: "blue".to_owned(),
+            entities: Some(vec![]),
+            ..info(MODE_CREATE, "\"[B] \"")
+        }));
+        registry.apply(&round_trip(entities(MODE_ADD_ENTITIES, "red", &["jeb_"])));
+
+        let red = registry.team("red").unwrap();
+        assert_eq!(red.prefix, Chat("\"[Red] \"".to_owned()));
+        assert_eq!(red.color, TeamColor::Red);
+        assert_eq!(registry.team_of("jeb_").unwrap().0, "red");
+
+        // Moving to another team leaves the first.
+        registry.apply(&round_trip(entities(MODE_ADD_ENTITIES, "blue", &["Notch"])));
+        assert_eq!(registry.team_of("Notch").unwrap().0, "blue");
+        assert_eq!(registry.team("red").unwrap().entities, ["jeb_"]);
+
+        registry.apply(&round_trip(entities(
+            MODE_REMOVE_ENTITIES,
+            "red",
+            &["jeb_"],
+        )));
+        assert!(registry.team_of("jeb_").is_none());
+        registry.apply(&round_trip(entities(MODE_REMOVE, "blue", &[])));
+        assert!(registry.team("blue").is_none());
+    }
+}
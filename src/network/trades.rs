@@ -0,0 +1,134 @@
+use crate::network::packets::TradeList;
+use crate::network::read_write::{read_optional, write_optional};
+use crate::network::{MinecraftIo, Slot};
+use anyhow::Result;
+use std::io::{Read, Write};
+
+/// A villager's or wandering trader's offer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trade {
+    pub input_item_1: Slot,
+    pub output_item: Slot,
+    pub input_item_2: Option<Slot>,
+    pub trade_disabled: bool,
+    pub uses: i32,
+    pub max_uses: i32,
+    pub experience: i32,
+    pub special_price: i32,
+    pub price_multiplier: f32,
+    pub demand: i32,
+}
+
+impl MinecraftIo for Trade {
+    fn minecraft_write(&self, writer: &mut impl Write) -> Result<()> {
+        self.input_item_1.minecraft_write(writer)?;
+        self.output_item.minecraft_write(writer)?;
+        write_optional(&self.input_item_2, writer)?;
+        self.trade_disabled.minecraft_write(writer)?;
+        self.uses.minecraft_write(writer)?;
+        self.max_uses.minecraft_write(writer)?;
+        self.experience.minecraft_write(writer)?;
+        self.special_price.minecraft_write(writer)?;
+        self.price_multiplier.minecraft_write(writer)?;
+        self.demand.minecraft_write(writer)
+    }
+
+    fn minecraft_read(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            input_item_1: Slot::minecraft_read(reader)?,
+            output_item: Slot::minecraft_read(reader)?,
+            input_item_2: read_optional(reader)?,
+            trade_disabled: bool::minecraft_read(reader)?,
+            uses: i32::minecraft_read(reader)?,
+            max_uses: i32::minecraft_read(reader)?,
+            experience: i32::minecraft_read(reader)?,
+            special_price: i32::minecraft_read(reader)?,
+            price_multiplier: f32::minecraft_read(reader)?,
+            demand: i32::minecraft_read(reader)?,
+        })
+    }
+}
+
+impl Trade {
+    /// The number of the first input item the trade costs once demand and
+    /// the special price are applied, never less than one.
+    pub fn price(&self) -> i32 {
+        let base = self.input_item_1.count() as i32;
+        let demand = (base as f32 * self.demand.max(0) as f32 * self.price_multiplier) as i32;
+        (base + demand + self.special_price).clamp(1, 64)
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.trade_disabled && self.uses < self.max_uses
+    }
+}
+
+impl TradeList {
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Direction, LengthPrefixedArray, Packet, State, VarInt};
+
+    fn item(id: i32, count: i8) -> Slot {
+        Slot::Item {
+            id: VarInt(id),
+            count,
+            nbt: None,
+        }
+    }
+
+    #[test]
+    fn test_trade_list_round_trip() {
+        let trades = vec![
+            Trade {
+                input_item_1: item(687, 20),
+                output_item: item(720, 1),
+                max_uses: 16,
+                experience: 2,
+                price_multiplier: 0.05,
+                demand: 4,
+                ..Trade::default()
+            },
+            Trade {
+                input_item_1: item(687, 5),
+                output_item: item(776, 1),
+                input_item_2: Some(item(720, 1)),
+                trade_disabled: true,
+                uses: 12,
+                max_uses: 12,
+                special_price: -2,
+                ..Trade::default()
+            },
+        ];
+        let packet = TradeList {
+            window_id: VarInt(3),
+            trades: LengthPrefixedArray::from_vec(trades.clone()),
+            villager_level: VarInt(2),
+            experience: VarInt(10),
+            is_regular_villager: true,
+            can_restock: true,
+        };
+        let mut payload = vec![];
+        Packet::TradeList(packet)
+            .write_payload(&mut payload)
+            .unwrap();
+        let Packet::TradeList(read) =
+            Packet::read_payload(State::Play, Direction::ClientBound, &payload).unwrap()
+        else {
+            panic!("expected TradeList");
+        };
+        assert_eq!(read.trades(), trades);
+        assert_eq!(read.villager_level, VarInt(2));
+        assert!(read.can_restock);
+
+        assert_eq!(read.trades()[0].price(), 24);
+        assert!(read.trades()[0].is_available());
+        assert_eq!(read.trades()[1].price(), 3);
+        assert!(!read.trades()[1].is_available());
+    }
+}
@@ -1,6 +1,8 @@
-use super::{VarInt, VarLong};
+use super::enums::ModifierOperation;
+use super::{ArrayError, VarInt, VarLong};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use uuid::Uuid;
 
 #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub struct Position {
@@ -20,6 +22,48 @@ pub enum Slot {
     },
 }
 
+impl Slot {
+    pub fn item_id(&self) -> Option<VarInt> {
+        match self {
+            Self::Nothing => None,
+            Self::Item { id, .. } => Some(*id),
+        }
+    }
+
+    pub fn count(&self) -> i8 {
+        match self {
+            Self::Nothing => 0,
+            Self::Item { count, .. } => *count,
+        }
+    }
+}
+
+/// An optional value sent after a boolean saying whether it is present.
+/// Optional packet fields that depend on other fields are `Option`s with a
+/// `when` condition instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefixedOption<T>(pub Option<T>);
+
+impl<T> Default for PrefixedOption<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T> From<Option<T>> for PrefixedOption<T> {
+    fn from(value: Option<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for PrefixedOption<T> {
+    type Target = Option<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub struct Identifier(pub String);
 
@@ -44,23 +88,105 @@ pub struct Angle(pub f32);
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct Nbt(pub nbt::Blob);
 
-pub type BitSet = ();
-pub type CommandNode = ();
-pub type Statistic = ();
-pub type EntityMetadata = ();
-pub type EntityProperty = ();
-pub type Recipe = ();
-pub type Tag = ();
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Statistic {
+    pub category_id: VarInt,
+    pub statistic_id: VarInt,
+    pub value: VarInt,
+}
+
+/// An entity attribute, like `generic.movement_speed`, with the modifiers
+/// applied to its base value.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct EntityProperty {
+    pub key: Identifier,
+    pub value: f64,
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct AttributeModifier {
+    pub uuid: Uuid,
+    pub amount: f64,
+    pub operation: ModifierOperation,
+}
+
 pub type BossBarAction = ();
 
+/// An entity's equipment by slot (main hand, off hand, boots, leggings,
+/// chestplate, helmet). Sent with no length: the top bit of each slot number
+/// says whether another entry follows.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Equipment(pub Vec<(i8, Slot)>);
+
+/// A set of bit indices, stored the way the protocol sends it: as longs, the
+/// lowest bit of the first long being index 0.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct BitSet(pub Vec<u64>);
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.0
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        let word = index / 64;
+        if word >= self.0.len() {
+            if !value {
+                return;
+            }
+            self.0.resize(word + 1, 0);
+        }
+        if value {
+            self.0[word] |= 1 << (index % 64);
+        } else {
+            self.0[word] &= !(1 << (index % 64));
+        }
+    }
+
+    /// The number of indices in the set.
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The indices in the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(word_index, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| word_index * 64 + bit)
+        })
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for index in iter {
+            set.set(index, true);
+        }
+        set
+    }
+}
+
+/// The type of a `LengthPrefixedArray`'s length prefix.
 pub trait Index {
-    fn into_index(self) -> usize;
+    fn into_index(self) -> anyhow::Result<usize>;
     fn to_value(index: usize) -> Self;
 }
 
 impl Index for VarInt {
-    fn into_index(self) -> usize {
-        self.0 as usize
+    fn into_index(self) -> anyhow::Result<usize> {
+        if self.0 < 0 {
+            Err(ArrayError::NegativeLength(self.0 as i64))?;
+        }
+        Ok(self.0 as usize)
     }
 
     fn to_value(index: usize) -> Self {
@@ -68,6 +194,16 @@ impl Index for VarInt {
     }
 }
 
+impl Index for u8 {
+    fn into_index(self) -> anyhow::Result<usize> {
+        Ok(self as usize)
+    }
+
+    fn to_value(index: usize) -> Self {
+        index as u8
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct LengthPrefixedArray<T: Index, U> {
     pub value: Vec<U>,
@@ -126,38 +262,6 @@ impl DerefMut for ByteArray {
 }
 
 /*
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
-pub struct BitSet {}
-
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
-pub struct CommandNode {}
-
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
-pub struct Statistic {}
-
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
-pub struct EntityMetadata {
-    // complicated.
-}
-
-#[derive(Clone, Default, Debug, PartialEq)]
-pub struct EntityProperty {
-    key: Identifier,
-    value: f64,
-    modifier_count: VarInt,
-    modifiers: Vec<()>, // TODO
-}
-
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
-pub struct Recipe {
-    // complicated
-}
-
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
-pub struct Tag {
-    // complicated
-}
-
 #[derive(Clone, Default, Debug, PartialEq)]
 pub enum BossBarAction {
     Add {
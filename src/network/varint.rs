@@ -25,7 +25,7 @@ impl Error for VarIntError {}
 
 fn read_one(reader: &mut impl Read) -> Option<u8> {
     let v = &mut [0u8];
-    reader.read(v).ok()?;
+    reader.read_exact(v).ok()?;
     Some(v[0])
 }
 
@@ -38,7 +38,7 @@ impl VarInt {
             if value & !SEGMENT_BITS == 0 {
                 buffer[index] = value as u8;
                 index += 1;
-                writer.write(&buffer[0..index])?;
+                writer.write_all(&buffer[0..index])?;
                 return Ok(());
             }
 
@@ -66,6 +66,37 @@ impl VarInt {
         }
         Ok(Self(value as i32))
     }
+
+    /// Decodes a VarInt from the front of `bytes` and advances past it. Much
+    /// faster than `read` for data already in memory, particularly for the
+    /// single byte values most VarInts are.
+    pub fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        if let Some(&byte) = bytes.first() {
+            if byte & CONTINUE_BIT == 0 {
+                *bytes = &bytes[1..];
+                return Ok(Self(byte as i32));
+            }
+        }
+        let (value, length) = decode(bytes, 5)?;
+        *bytes = &bytes[length..];
+        Ok(Self(value as i32))
+    }
+}
+
+/// Decodes at most `max_length` bytes of a VarInt or VarLong, returning the
+/// value and how many bytes it took.
+fn decode(bytes: &[u8], max_length: usize) -> Result<(u64, usize)> {
+    let mut value = 0;
+    for (i, &byte) in bytes.iter().take(max_length).enumerate() {
+        value |= (byte as u64 & SEGMENT_BITS as u64) << (7 * i);
+        if byte & CONTINUE_BIT == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    if bytes.len() < max_length {
+        Err(VarIntError::NotEnoughBytes)?;
+    }
+    Err(VarIntError::TooManyBytes)?
 }
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
@@ -80,7 +111,7 @@ impl VarLong {
             if value & !(SEGMENT_BITS as u64) == 0 {
                 buffer[index] = value as u8;
                 index += 1;
-                writer.write(&buffer[0..index])?;
+                writer.write_all(&buffer[0..index])?;
                 return Ok(());
             }
 
@@ -102,12 +133,19 @@ impl VarLong {
                 break;
             }
             position += 7;
-            if position > 32 {
+            if position > 63 {
                 Err(VarIntError::TooManyBytes)?;
             }
         }
         Ok(Self(value as i64))
     }
+
+    /// Decodes a VarLong from the front of `bytes` and advances past it.
+    pub fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let (value, length) = decode(bytes, 10)?;
+        *bytes = &bytes[length..];
+        Ok(Self(value as i64))
+    }
 }
 
 #[cfg(test)]
@@ -183,4 +221,45 @@ mod tests {
             vec.clear();
         }
     }
+
+    #[test]
+    fn test_varlong_decode() {
+        for &(number, bytes) in VARLONG_CONVERSIONS {
+            let mut reader = Cursor::new(bytes);
+            let varlong_value = VarLong::read(&mut reader).unwrap();
+            assert_eq!(number, varlong_value.0);
+        }
+
+        let too_long = [255; 11];
+        assert!(VarLong::read(&mut Cursor::new(too_long)).is_err());
+    }
+
+    #[test]
+    fn test_decode_slice() {
+        for &(number, bytes) in VARINT_CONVERSIONS {
+            let with_next = [bytes, &[0x2a]].concat();
+            let mut slice = &with_next[..];
+            assert_eq!(VarInt::decode(&mut slice).unwrap(), VarInt(number));
+            assert_eq!(slice, &[0x2a]);
+        }
+        for &(number, bytes) in VARLONG_CONVERSIONS {
+            let mut slice = bytes;
+            assert_eq!(VarLong::decode(&mut slice).unwrap(), VarLong(number));
+            assert!(slice.is_empty());
+        }
+
+        let mut truncated: &[u8] = &[0x80, 0x80];
+        let error = VarInt::decode(&mut truncated).unwrap_err();
+        assert!(matches!(
+            error.downcast::<VarIntError>().unwrap(),
+            VarIntError::NotEnoughBytes
+        ));
+        assert_eq!(truncated.len(), 2);
+        let error = VarInt::decode(&mut &[0xff; 6][..]).unwrap_err();
+        assert!(matches!(
+            error.downcast::<VarIntError>().unwrap(),
+            VarIntError::TooManyBytes
+        ));
+        assert!(VarInt::decode(&mut &[][..]).is_err());
+    }
 }
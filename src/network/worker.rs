@@ -0,0 +1,428 @@
+use crate::network::connection::Connection;
+use crate::network::machine::ProtocolMachine;
+use crate::network::packets::{FrameError, ProtocolError};
+use crate::network::session::{Account, SessionService};
+use crate::network::{Direction, Packet, State};
+use anyhow::Result;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How much is read from the stream at a time.
+const READ_SIZE: usize = 8192;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorkerError {
+    /// The outbound queue is full; the packet was not sent.
+    QueueFull,
+    /// The worker has stopped, and will send nothing more.
+    Stopped,
+}
+
+impl Display for WorkerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for WorkerError {}
+
+// Packets are moved by value everywhere else too; the rare error is small.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum NetworkEvent {
+    Packet(Packet),
+    /// A packet arrived whole but could not be decoded. It is skipped, and
+    /// the connection carries on.
+    DecodeError(anyhow::Error),
+    /// The connection was closed or failed. This is the last event.
+    ConnectionLost(anyhow::Error),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WorkerConfig {
+    /// Events decoded but not yet polled. Once this many are waiting the
+    /// worker stops reading, leaving the server to wait on TCP flow control.
+    pub inbound_capacity: usize,
+    /// Packets waiting to be sent before `send` refuses more.
+    pub outbound_capacity: usize,
+    /// The most events one call to `poll` returns, so a burst of packets is
+    /// spread over several frames.
+    pub packets_per_frame: usize,
+    /// How long a read waits for the server before the worker checks for
+    /// packets to send.
+    pub poll_interval: Duration,
+}
+
+impl WorkerConfig {
+    pub const DEFAULT: Self = Self {
+        inbound_capacity: 4096,
+        outbound_capacity: 256,
+        packets_per_frame: 512,
+        poll_interval: Duration::from_millis(5),
+    };
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A thread that owns a connection's stream, decoding packets off the render
+/// thread. Packets received are polled once per frame; packets to send are
+/// queued and written by the thread.
+pub struct NetworkWorker {
+    config: WorkerConfig,
+    events: Receiver<NetworkEvent>,
+    outbound: SyncSender<Packet>,
+    handle: JoinHandle<()>,
+}
+
+impl NetworkWorker {
+    /// Connects to a server, logs in as `account` and hands the connection to
+    /// a new worker.
+    pub fn connect(
+        address: &str,
+        port: u16,
+        account: &Account,
+        session: &dyn SessionService,
+        config: WorkerConfig,
+    ) -> Result<Self> {
+        let stream = TcpStream::connect((address, port))?;
+        let socket = stream.try_clone()?;
+        let mut connection = Connection::from_stream(stream, Direction::ClientBound)?;
+        connection.handshake(address, port, State::Login)?;
+        // Logging in waits on the server, which may itself wait on the session
+        // server, so the reads only start timing out once it is done.
+        connection.login(account, session)?;
+        socket.set_read_timeout(Some(config.poll_interval))?;
+        Ok(Self::spawn(connection, config))
+    }
+
+    /// Moves `connection` to a new worker thread. Its reader should time out
+    /// (`TcpStream::set_read_timeout`) after about `config.poll_interval`;
+    /// while a read blocks, queued packets wait to be sent.
+    pub fn spawn(connection: Connection, config: WorkerConfig) -> Self {
+        let (event_sender, events) = mpsc::sync_channel(config.inbound_capacity);
+        let (outbound, outbound_receiver) = mpsc::sync_channel(config.outbound_capacity);
+        let (machine, reader, writer) = connection.into_parts();
+        let handle = thread::spawn(move || {
+            let mut worker = Worker {
+                machine,
+                reader,
+                writer,
+                events: event_sender,
+                outbound: outbound_receiver,
+                config,
+            };
+            if let Err(error) = worker.run() {
+                let _ = worker.events.send(NetworkEvent::ConnectionLost(error));
+            }
+        });
+        Self {
+            config,
+            events,
+            outbound,
+            handle,
+        }
+    }
+
+    pub fn config(&self) -> WorkerConfig {
+        self.config
+    }
+
+    /// Takes the events received since the last poll, at most
+    /// `packets_per_frame` of them. Never blocks.
+    pub fn poll(&self) -> impl Iterator<Item = NetworkEvent> + '_ {
+        std::iter::from_fn(|| self.events.try_recv().ok()).take(self.config.packets_per_frame)
+    }
+
+    /// Queues `packet` to be sent. Never blocks.
+    pub fn send(&self, packet: Packet) -> Result<()> {
+        match self.outbound.try_send(packet) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(WorkerError::QueueFull)?,
+            Err(TrySendError::Disconnected(_)) => Err(WorkerError::Stopped)?,
+        }
+    }
+
+    /// Whether the worker thread has exited.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+/// Whether a packet that failed to decode can be skipped. The machine has
+/// moved past the frame by then, so unless the framing itself, the stream or
+/// its decryption failed, the next packet can still be read.
+fn is_recoverable(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.is::<ProtocolError>()
+            || matches!(cause.downcast_ref(), Some(FrameError::UnknownPacket { .. }))
+    })
+}
+
+/// The state of the worker thread.
+struct Worker {
+    machine: ProtocolMachine,
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    events: SyncSender<NetworkEvent>,
+    outbound: Receiver<Packet>,
+    config: WorkerConfig,
+}
+
+impl Worker {
+    /// Runs until the connection fails, returning the reason, or until the
+    /// `NetworkWorker` is dropped.
+    fn run(&mut self) -> Result<()> {
+        let mut buffer = [0; READ_SIZE];
+        let mut pending = None;
+        loop {
+            if !self.send_queued()? {
+                return Ok(());
+            }
+
+            // Packets already received go out before reading more, so a full
+            // queue holds back the stream.
+            if pending.is_none() {
+                pending = match self.machine.next_packet() {
+                    Ok(packet) => packet.map(NetworkEvent::Packet),
+                    Err(error) if is_recoverable(&error) => Some(NetworkEvent::DecodeError(error)),
+                    Err(error) => return Err(error),
+                };
+            }
+            if let Some(event) = pending.take() {
+                match self.events.try_send(event) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(event)) => {
+                        pending = Some(event);
+                        thread::sleep(self.config.poll_interval);
+                    }
+                    Err(TrySendError::Disconnected(_)) => return Ok(()),
+                }
+                continue;
+            }
+
+            match self.reader.read(&mut buffer) {
+                Ok(0) => Err(std::io::Error::from(ErrorKind::UnexpectedEof))?,
+                Ok(count) => self.machine.receive(&buffer[..count]),
+                Err(error)
+                    if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => Err(error)?,
+            }
+        }
+    }
+
+    /// Writes the packets queued to send, returning false once the
+    /// `NetworkWorker` has been dropped.
+    fn send_queued(&mut self) -> Result<bool> {
+        loop {
+            match self.outbound.try_recv() {
+                Ok(packet) => self.machine.send(&packet)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(false),
+            }
+        }
+        if !self.machine.outgoing().is_empty() {
+            self.writer.write_all(self.machine.outgoing())?;
+            self.writer.flush()?;
+            self.machine.take_outgoing();
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::limits::Limits;
+    use crate::network::packets::{ChatMessage, KeepAliveRequest, KeepAliveResponse, LoginSuccess};
+    use crate::network::session::HttpSessionService;
+    use crate::network::Chat;
+    use std::net::TcpListener;
+    use std::time::Instant;
+    use uuid::Uuid;
+
+    fn play_pair(config: WorkerConfig) -> (NetworkWorker, Connection) {
+        let (client, server) = play_connections(config);
+        (NetworkWorker::spawn(client, config), server)
+    }
+
+    fn play_connections(config: WorkerConfig) -> (Connection, Connection) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.set_read_timeout(Some(config.poll_interval)).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let mut client = Connection::from_stream(client, Direction::ClientBound).unwrap();
+        let mut server = Connection::from_stream(server, Direction::ServerBound).unwrap();
+        client.set_state(State::Play);
+        server.set_state(State::Play);
+        (client, server)
+    }
+
+    /// Polls until `count` events have arrived, checking the frame budget.
+    fn poll_events(worker: &NetworkWorker, count: usize) -> Vec<NetworkEvent> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut events = vec![];
+        while events.len() < count {
+            assert!(Instant::now() < deadline, "timed out");
+            let frame: Vec<_> = worker.poll().collect();
+            assert!(frame.len() <= worker.config().packets_per_frame);
+            events.extend(frame);
+            thread::sleep(Duration::from_millis(1));
+        }
+        events
+    }
+
+    #[test]
+    fn test_packets_both_ways() {
+        let config = WorkerConfig {
+            inbound_capacity: 4,
+            packets_per_frame: 3,
+            ..WorkerConfig::DEFAULT
+        };
+        let (worker, mut server) = play_pair(config);
+        for id in 0..20 {
+            server
+                .write_packet(&Packet::KeepAliveRequest(KeepAliveRequest {
+                    keep_alive_id: id,
+                }))
+                .unwrap();
+        }
+        let events = poll_events(&worker, 20);
+        for (id, event) in events.into_iter().enumerate() {
+            assert!(matches!(
+                event,
+                NetworkEvent::Packet(Packet::KeepAliveRequest(KeepAliveRequest { keep_alive_id }))
+                    if keep_alive_id == id as i64
+            ));
+        }
+
+        worker
+            .send(Packet::KeepAliveResponse(KeepAliveResponse {
+                keep_alive_id: 7,
+            }))
+            .unwrap();
+        assert!(matches!(
+            server.read_packet().unwrap(),
+            Packet::KeepAliveResponse(KeepAliveResponse { keep_alive_id: 7 })
+        ));
+    }
+
+    #[test]
+    fn test_connection_lost() {
+        let (worker, server) = play_pair(WorkerConfig::DEFAULT);
+        drop(server);
+        let events = poll_events(&worker, 1);
+        assert!(matches!(events[..], [NetworkEvent::ConnectionLost(_)]));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !worker.is_finished() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+        let error = worker
+            .send(Packet::KeepAliveResponse(KeepAliveResponse {
+                keep_alive_id: 1,
+            }))
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<WorkerError>().unwrap(),
+            WorkerError::Stopped
+        );
+    }
+
+    #[test]
+    fn test_decode_error_is_skipped() {
+        let (mut client, mut server) = play_connections(WorkerConfig::DEFAULT);
+        client.set_limits(Limits {
+            max_chat_length: 8,
+            ..Limits::DEFAULT
+        });
+        let worker = NetworkWorker::spawn(client, WorkerConfig::DEFAULT);
+        server
+            .write_packet(&Packet::ChatMessage(ChatMessage {
+                chat_json: Chat("\"far too long for the limit\"".to_owned()),
+                ..ChatMessage::default()
+            }))
+            .unwrap();
+        server
+            .write_packet(&Packet::KeepAliveRequest(KeepAliveRequest {
+                keep_alive_id: 3,
+            }))
+            .unwrap();
+
+        let events = poll_events(&worker, 2);
+        let NetworkEvent::DecodeError(error) = &events[0] else {
+            panic!("expected a decode error, got {:?}", events[0]);
+        };
+        assert_eq!(
+            error.downcast_ref::<ProtocolError>().unwrap().packet,
+            "ChatMessage"
+        );
+        assert!(matches!(
+            events[1],
+            NetworkEvent::Packet(Packet::KeepAliveRequest(KeepAliveRequest {
+                keep_alive_id: 3
+            }))
+        ));
+        assert!(!worker.is_finished());
+    }
+
+    #[test]
+    fn test_connect_waits_for_slow_login() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut server = Connection::from_stream(stream, Direction::ServerBound).unwrap();
+            assert!(matches!(
+                server.read_packet().unwrap(),
+                Packet::Handshake(_)
+            ));
+            assert!(matches!(
+                server.read_packet().unwrap(),
+                Packet::LoginStart(_)
+            ));
+            // Far longer than the poll interval, as when an online-mode
+            // server checks the session server.
+            thread::sleep(Duration::from_millis(200));
+            server
+                .write_packet(&Packet::LoginSuccess(LoginSuccess {
+                    uuid: Uuid::from_u128(1),
+                    usename: "tester".to_owned(),
+                }))
+                .unwrap();
+            server
+                .write_packet(&Packet::KeepAliveRequest(KeepAliveRequest {
+                    keep_alive_id: 5,
+                }))
+                .unwrap();
+            server
+        });
+
+        let worker = NetworkWorker::connect(
+            "127.0.0.1",
+            port,
+            &Account::offline("tester"),
+            &HttpSessionService::default(),
+            WorkerConfig::DEFAULT,
+        )
+        .unwrap();
+        let events = poll_events(&worker, 1);
+        assert!(matches!(
+            events[..],
+            [NetworkEvent::Packet(Packet::KeepAliveRequest(
+                KeepAliveRequest { keep_alive_id: 5 }
+            ))]
+        ));
+        let _server = server.join().unwrap();
+    }
+}
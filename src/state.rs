@@ -2,9 +2,12 @@ use crate::camera::Camera;
 use crate::camera::CameraController;
 use crate::chunk::Chunk;
 use crate::mesh::{DrawMesh, Mesh, Vertex, Vertex2};
+use crate::network::worker::{NetworkEvent, NetworkWorker};
+use crate::network::{packets, Packet};
 use crate::texture::Texture;
 use crate::uniform::Uniform;
 use crate::vertex::VertexLayout;
+use crate::world::{World, OVERWORLD_MIN_Y};
 use winit::dpi::PhysicalSize;
 use winit::event::WindowEvent;
 use winit::window::Window;
@@ -24,6 +27,10 @@ pub struct State {
     mesh: Mesh,
 
     camera: Camera,
+
+    /// The server connection, once there is one.
+    network: Option<NetworkWorker>,
+    world: World,
 }
 
 impl State {
@@ -63,8 +70,7 @@ impl State {
             .formats
             .iter()
             .copied()
-            .filter(|f| f.describe().srgb)
-            .next()
+            .find(|f| f.describe().srgb)
             .unwrap_or(surface_caps.formats[0]);
 
         let config = wgpu::SurfaceConfiguration {
@@ -123,6 +129,9 @@ impl State {
             mesh,
 
             camera,
+
+            network: None,
+            world: World::new(OVERWORLD_MIN_Y),
         }
     }
 
@@ -149,8 +158,55 @@ impl State {
         self.camera.input(event)
     }
 
+    /// Hands a connected server to the render loop; its packets are handled
+    /// from the next `update`.
+    pub fn set_network(&mut self, network: NetworkWorker) {
+        self.network = Some(network);
+    }
+
     pub fn update(&mut self) {
         self.camera.update(&self.config, &self.queue);
+        self.update_network();
+    }
+
+    /// Handles the packets received since the last frame, up to the worker's
+    /// per-frame budget.
+    fn update_network(&mut self) {
+        let Some(network) = &self.network else {
+            return;
+        };
+        let events: Vec<_> = network.poll().collect();
+        for event in events {
+            match event {
+                NetworkEvent::Packet(packet) => {
+                    if let Err(e) = self.handle_packet(packet) {
+                        log::warn!("failed to handle packet: {e:#}");
+                    }
+                }
+                NetworkEvent::DecodeError(e) => log::warn!("skipped packet: {e:#}"),
+                NetworkEvent::ConnectionLost(e) => {
+                    log::error!("connection lost: {e:#}");
+                    self.network = None;
+                }
+            }
+        }
+    }
+
+    fn handle_packet(&mut self, packet: Packet) -> anyhow::Result<()> {
+        match packet {
+            Packet::KeepAliveRequest(request) => {
+                if let Some(network) = &self.network {
+                    network.send(Packet::KeepAliveResponse(packets::KeepAliveResponse {
+                        keep_alive_id: request.keep_alive_id,
+                    }))?;
+                }
+            }
+            Packet::ChunkDataAndUpdateLight(packet) => self.world.load_chunk_data(&packet)?,
+            Packet::BlockChange(packet) => self.world.apply_block_change(&packet),
+            Packet::MultiBlockChange(packet) => self.world.apply_multi_block_change(&packet),
+            _ => {}
+        }
+        Ok(())
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -204,7 +260,7 @@ impl State {
             });
 
             render_pass.set_bind_group(0, self.texture.bind_group.as_ref().unwrap(), &[]);
-            render_pass.set_bind_group(1, &self.camera.bind_group(), &[]);
+            render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.draw_mesh(&self.mesh);
         }
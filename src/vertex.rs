@@ -66,22 +66,23 @@ macro_rules! vertex_struct {
             )*
         }
 
-        impl crate::vertex::Vertex for $Vertex {}
+        impl $crate::vertex::Vertex for $Vertex {}
 
-        impl crate::vertex::VertexLayout for $Vertex {
+        impl $crate::vertex::VertexLayout for $Vertex {
+            #[allow(clippy::vec_init_then_push)]
             fn layout<'a>() -> wgpu::VertexBufferLayout<'a> {
                 use once_cell::sync::OnceCell;
                 static INSTANCE: OnceCell<wgpu::VertexBufferLayout<'static>> = OnceCell::new();
                 INSTANCE.get_or_init(|| {
                     let mut offset = 0;
-                    let mut location = crate::default!($($location)?, 0);
+                    let mut location = $crate::default!($($location)?, 0);
                     let mut attributes = vec![];
 
                     $(
                         attributes.push(wgpu::VertexAttribute {
                             offset: offset as wgpu::BufferAddress,
                             shader_location: location,
-                            format: <$type as crate::vertex::VertexFormat>::vertex_format(),
+                            format: <$type as $crate::vertex::VertexFormat>::vertex_format(),
                         });
                         location += 1;
                         offset += std::mem::size_of::<$type>();
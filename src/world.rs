@@ -0,0 +1,299 @@
+use crate::chunk::Chunk;
+use crate::network::chunk_data::{ChunkSection, SECTION_WIDTH};
+use crate::network::packets::{BlockChange, ChunkDataAndUpdateLight, MultiBlockChange};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// The lowest block of the overworld since 1.18.
+pub const OVERWORLD_MIN_Y: i32 = -64;
+
+/// The blocks received from a server, stored as block state IDs in the
+/// renderer's `Chunk`s.
+pub struct World {
+    min_y: i32,
+    chunks: HashMap<(i32, i32, i32), Chunk>,
+}
+
+/// Splits a block coordinate into the coordinate of the `Chunk` containing it
+/// and the offset within that chunk.
+fn split(coordinate: i32, size: usize) -> (i32, usize) {
+    let size = size as i32;
+    (
+        coordinate.div_euclid(size),
+        coordinate.rem_euclid(size) as usize,
+    )
+}
+
+impl World {
+    pub fn new(min_y: i32) -> Self {
+        Self {
+            min_y,
+            chunks: HashMap::new(),
+        }
+    }
+
+    pub fn chunk(&self, position: (i32, i32, i32)) -> Option<&Chunk> {
+        self.chunks.get(&position)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    pub fn chunks_mut(&mut self) -> impl Iterator<Item = &mut Chunk> {
+        self.chunks.values_mut()
+    }
+
+    /// The block state at a block position, if that part of the world is
+    /// loaded.
+    pub fn block(&self, x: i32, y: i32, z: i32) -> Option<u16> {
+        let (chunk_x, x) = split(x, Chunk::SIZE_X);
+        let (chunk_y, y) = split(y, Chunk::SIZE_Y);
+        let (chunk_z, z) = split(z, Chunk::SIZE_Z);
+        let chunk = self.chunks.get(&(chunk_x, chunk_y, chunk_z))?;
+        Some(chunk.get(x, y, z))
+    }
+
+    /// Sets a block in a loaded chunk, marking the meshes it affects dirty.
+    /// Returns false, changing nothing, if the block is not loaded.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: u16) -> bool {
+        let (chunk_x, local_x) = split(x, Chunk::SIZE_X);
+        let (chunk_y, local_y) = split(y, Chunk::SIZE_Y);
+        let (chunk_z, local_z) = split(z, Chunk::SIZE_Z);
+        let Some(chunk) = self.chunks.get_mut(&(chunk_x, chunk_y, chunk_z)) else {
+            return false;
+        };
+        if chunk.get(local_x, local_y, local_z) == state {
+            return true;
+        }
+        chunk.set(local_x, local_y, local_z, state);
+
+        // A block on the edge of a chunk hides or reveals faces of the
+        // neighbour it touches.
+        let edges = [
+            (local_x == 0, (-1, 0, 0)),
+            (local_x == Chunk::SIZE_X - 1, (1, 0, 0)),
+            (local_y == 0, (0, -1, 0)),
+            (local_y == Chunk::SIZE_Y - 1, (0, 1, 0)),
+            (local_z == 0, (0, 0, -1)),
+            (local_z == Chunk::SIZE_Z - 1, (0, 0, 1)),
+        ];
+        for (_, (dx, dy, dz)) in edges.into_iter().filter(|(on_edge, _)| *on_edge) {
+            if let Some(neighbour) =
+                self.chunks
+                    .get_mut(&(chunk_x + dx, chunk_y + dy, chunk_z + dz))
+            {
+                neighbour.mark_dirty();
+            }
+        }
+        true
+    }
+
+    /// Sets a block while loading chunk data, creating its `Chunk` if
+    /// necessary.
+    fn load_block(&mut self, x: i32, y: i32, z: i32, state: u16) {
+        let (chunk_x, x) = split(x, Chunk::SIZE_X);
+        let (chunk_y, y) = split(y, Chunk::SIZE_Y);
+        let (chunk_z, z) = split(z, Chunk::SIZE_Z);
+        let position = (chunk_x, chunk_y, chunk_z);
+        self.chunks
+            .entry(position)
+            .or_insert_with(|| Chunk::new(position))
+            .set(x, y, z, state);
+    }
+
+    /// Copies the sections of the 16 block wide column at `chunk_x`,
+    /// `chunk_z` into the world, the first section at the world's lowest y.
+    pub fn load_column(&mut self, chunk_x: i32, chunk_z: i32, sections: &[ChunkSection]) {
+        let width = SECTION_WIDTH as i32;
+        for (index, section) in sections.iter().enumerate() {
+            let base_x = chunk_x * width;
+            let base_y = self.min_y + index as i32 * width;
+            let base_z = chunk_z * width;
+            for y in 0..SECTION_WIDTH {
+                for z in 0..SECTION_WIDTH {
+                    for x in 0..SECTION_WIDTH {
+                        self.load_block(
+                            base_x + x as i32,
+                            base_y + y as i32,
+                            base_z + z as i32,
+                            section.block_state(x, y, z) as u16,
+                        );
+                    }
+                }
+            }
+        }
+
+        // The blocks on the sides of the column hide or reveal faces of the
+        // columns next to it.
+        let (min_x, max_x) = (chunk_x * width, (chunk_x + 1) * width - 1);
+        let (min_y, max_y) = (self.min_y, self.min_y + sections.len() as i32 * width - 1);
+        let (min_z, max_z) = (chunk_z * width, (chunk_z + 1) * width - 1);
+        self.mark_dirty((min_x - 1, min_y, min_z), (min_x - 1, max_y, max_z));
+        self.mark_dirty((max_x + 1, min_y, min_z), (max_x + 1, max_y, max_z));
+        self.mark_dirty((min_x, min_y, min_z - 1), (max_x, max_y, min_z - 1));
+        self.mark_dirty((min_x, min_y, max_z + 1), (max_x, max_y, max_z + 1));
+    }
+
+    /// Marks the loaded chunks holding any block between `min` and `max`,
+    /// inclusive, dirty.
+    fn mark_dirty(&mut self, min: (i32, i32, i32), max: (i32, i32, i32)) {
+        let (min_x, _) = split(min.0, Chunk::SIZE_X);
+        let (min_y, _) = split(min.1, Chunk::SIZE_Y);
+        let (min_z, _) = split(min.2, Chunk::SIZE_Z);
+        let (max_x, _) = split(max.0, Chunk::SIZE_X);
+        let (max_y, _) = split(max.1, Chunk::SIZE_Y);
+        let (max_z, _) = split(max.2, Chunk::SIZE_Z);
+        for chunk_x in min_x..=max_x {
+            for chunk_y in min_y..=max_y {
+                for chunk_z in min_z..=max_z {
+                    if let Some(chunk) = self.chunks.get_mut(&(chunk_x, chunk_y, chunk_z)) {
+                        chunk.mark_dirty();
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn load_chunk_data(&mut self, packet: &ChunkDataAndUpdateLight) -> Result<()> {
+        let sections = packet.sections()?;
+        self.load_column(packet.chunk_x, packet.chunk_z, &sections);
+        Ok(())
+    }
+
+    pub fn apply_block_change(&mut self, packet: &BlockChange) {
+        let location = packet.location;
+        let state = packet.block_state() as u16;
+        self.set_block(location.x, location.y, location.z, state);
+    }
+
+    pub fn apply_multi_block_change(&mut self, packet: &MultiBlockChange) {
+        for (location, state) in packet.changes() {
+            self.set_block(location.x, location.y, location.z, state as u16);
+        }
+    }
+
+    /// Regenerates the meshes of chunks that changed since their last mesh,
+    /// returning how many were rebuilt.
+    pub fn generate_dirty_meshes(&mut self) -> usize {
+        let mut count = 0;
+        for chunk in self.chunks.values_mut().filter(|chunk| chunk.is_dirty()) {
+            chunk.generate_mesh();
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::chunk_data::{
+        encode_block_entry, encode_section_position, BIOMES_PER_SECTION, BLOCKS_PER_SECTION,
+    };
+    use crate::network::{Position, VarInt};
+
+    fn section(state: impl Fn(usize, usize, usize) -> u32) -> ChunkSection {
+        let mut block_states = vec![0; BLOCKS_PER_SECTION];
+        for (i, block) in block_states.iter_mut().enumerate() {
+            *block = state(i % 16, i / 256, i / 16 % 16);
+        }
+        ChunkSection {
+            block_count: 0,
+            block_states,
+            biomes: vec![0; BIOMES_PER_SECTION],
+        }
+    }
+
+    #[test]
+    fn test_load_column() {
+        let mut world = World::new(OVERWORLD_MIN_Y);
+        let bedrock = section(|_, y, _| if y == 0 { 33 } else { 0 });
+        let marked = section(|x, y, z| if (x, y, z) == (1, 2, 3) { 7 } else { 0 });
+        // Negative column coordinates land in negative `Chunk`s.
+        world.load_column(-1, 2, &[bedrock, marked]);
+
+        assert_eq!(world.block(-16, -64, 32), Some(33));
+        assert_eq!(world.block(-1, -64, 47), Some(33));
+        assert_eq!(world.block(-16, -63, 32), Some(0));
+        assert_eq!(world.block(-15, -46, 35), Some(7));
+        assert_eq!(world.block(0, -64, 32), None);
+
+        let chunk = world.chunk((-1, -2, 1)).unwrap();
+        assert_eq!(chunk.get(16, 0, 0), 33);
+        assert_eq!(world.chunks().count(), 1);
+    }
+
+    fn dirty_chunks(world: &World) -> Vec<(i32, i32, i32)> {
+        let mut dirty: Vec<_> = world
+            .chunks()
+            .filter(|chunk| chunk.is_dirty())
+            .map(|chunk| chunk.position())
+            .collect();
+        dirty.sort();
+        dirty
+    }
+
+    #[test]
+    fn test_block_changes() {
+        let mut world = World::new(OVERWORLD_MIN_Y);
+        let empty = section(|_, _, _| 0);
+        for chunk_x in 0..4 {
+            world.load_column(chunk_x, 0, &[empty.clone(), empty.clone()]);
+        }
+        assert_eq!(dirty_chunks(&world), [(0, -2, 0), (1, -2, 0)]);
+        assert_eq!(world.generate_dirty_meshes(), 2);
+        assert!(dirty_chunks(&world).is_empty());
+
+        // Inside the chunk: only that chunk needs a new mesh.
+        world.apply_block_change(&BlockChange {
+            location: Position { x: 5, y: -60, z: 5 },
+            block_id: VarInt(1),
+        });
+        assert_eq!(world.block(5, -60, 5), Some(1));
+        assert_eq!(dirty_chunks(&world), [(0, -2, 0)]);
+        world.generate_dirty_meshes();
+
+        // On the edge between the two chunks: both do.
+        world.apply_multi_block_change(&MultiBlockChange {
+            chunk_section_position: encode_section_position((1, -4, 0)),
+            trust_light_edges: false,
+            blocks: vec![encode_block_entry(2, (15, 0, 0))],
+        });
+        assert_eq!(world.block(31, -64, 0), Some(2));
+        assert_eq!(dirty_chunks(&world), [(0, -2, 0), (1, -2, 0)]);
+        world.generate_dirty_meshes();
+
+        // Setting a block to what it already is changes nothing.
+        world.apply_block_change(&BlockChange {
+            location: Position {
+                x: 31,
+                y: -64,
+                z: 0,
+            },
+            block_id: VarInt(2),
+        });
+        assert!(dirty_chunks(&world).is_empty());
+
+        // Unloaded blocks are ignored.
+        assert!(!world.set_block(100, 0, 0, 1));
+        assert_eq!(world.block(100, 0, 0), None);
+    }
+
+    #[test]
+    fn test_adjacent_columns() {
+        let mut world = World::new(OVERWORLD_MIN_Y);
+        let column = [section(|_, _, _| 0)];
+        world.load_column(1, 0, &column);
+        world.generate_dirty_meshes();
+
+        // The new column touches the edge of the first one's chunk.
+        world.load_column(2, 0, &column);
+        assert_eq!(dirty_chunks(&world), [(0, -2, 0), (1, -2, 0)]);
+        world.generate_dirty_meshes();
+
+        // Only the chunks the new column touches are marked.
+        world.load_column(2, -1, &column);
+        assert_eq!(dirty_chunks(&world), [(1, -2, -1), (1, -2, 0)]);
+    }
+}